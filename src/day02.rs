@@ -34,28 +34,42 @@ struct Move {
 }
 #[derive(Default, Debug)]
 struct Position {
-    depth: i32,
-    horizontal: i32,
-    aim: i32,
+    depth: i64,
+    horizontal: i64,
+    aim: i64,
 }
 impl Position {
-    fn type1(&mut self, m: &Move) {
-        match m.dir {
-            Direction::Forward => self.horizontal += m.amount,
-            Direction::Down => self.depth += m.amount,
-            Direction::Up => self.depth -= m.amount,
-        }
+    fn type1(&mut self, m: &Move) -> anyhow::Result<()> {
+        let amount = m.amount as i64;
+        let updated = match m.dir {
+            Direction::Forward => self
+                .horizontal
+                .checked_add(amount)
+                .map(|h| self.horizontal = h),
+            Direction::Down => self.depth.checked_add(amount).map(|d| self.depth = d),
+            Direction::Up => self.depth.checked_sub(amount).map(|d| self.depth = d),
+        };
+        updated.ok_or_else(|| anyhow!("position overflows at {:?}", self))
+    }
+
+    fn type2(&mut self, m: &Move) -> anyhow::Result<()> {
+        let amount = m.amount as i64;
+        let updated = match m.dir {
+            Direction::Forward => self.horizontal.checked_add(amount).and_then(|h| {
+                self.depth = self.depth.checked_add(amount.checked_mul(self.aim)?)?;
+                self.horizontal = h;
+                Some(())
+            }),
+            Direction::Down => self.aim.checked_add(amount).map(|a| self.aim = a),
+            Direction::Up => self.aim.checked_sub(amount).map(|a| self.aim = a),
+        };
+        updated.ok_or_else(|| anyhow!("position overflows at {:?}", self))
     }
 
-    fn type2(&mut self, m: &Move) {
-        match m.dir {
-            Direction::Forward => {
-                self.horizontal += m.amount;
-                self.depth += m.amount * self.aim;
-            }
-            Direction::Down => self.aim += m.amount,
-            Direction::Up => self.aim -= m.amount,
-        }
+    fn score(&self) -> anyhow::Result<i64> {
+        self.depth
+            .checked_mul(self.horizontal)
+            .ok_or_else(|| anyhow!("score overflows at {:?}", self))
     }
 }
 
@@ -88,20 +102,20 @@ fn dir_parser(input: &str) -> IResult<&str, Direction> {
     ))(input)
 }
 
-fn score1(input: &[Move]) -> i32 {
+fn score1(input: &[Move]) -> anyhow::Result<i64> {
     let mut pos = Position::default();
     for m in input {
-        pos.type1(m);
+        pos.type1(m)?;
     }
-    pos.depth * pos.horizontal
+    pos.score()
 }
 
-fn score2(input: &[Move]) -> i32 {
+fn score2(input: &[Move]) -> anyhow::Result<i64> {
     let mut pos = Position::default();
     for m in input {
-        pos.type2(m);
+        pos.type2(m)?;
     }
-    pos.depth * pos.horizontal
+    pos.score()
 }
 
 #[cfg(test)]
@@ -120,8 +134,21 @@ mod test {
     #[test]
     fn small() -> anyhow::Result<()> {
         let input = parse_input(SMALL)?;
-        assert_eq!(score1(&input), 150);
-        assert_eq!(score2(&input), 900);
+        assert_eq!(score1(&input)?, 150);
+        assert_eq!(score2(&input)?, 900);
+        Ok(())
+    }
+
+    #[test]
+    fn overflow() -> anyhow::Result<()> {
+        let input = parse_input(
+            r"
+            down 2000000000
+            forward 2000000000
+            ",
+        )?;
+        assert_eq!(score1(&input)?, 4_000_000_000_000_000_000);
+        assert!(score2(&input).is_err());
         Ok(())
     }
 
@@ -129,8 +156,8 @@ mod test {
     fn normal() -> anyhow::Result<()> {
         let raw = std::fs::read_to_string("data/day02.input")?;
         let input = parse_input(&raw)?;
-        assert_eq!(score1(&input), 2073315);
-        assert_eq!(score2(&input), 1840311528);
+        assert_eq!(score1(&input)?, 2073315);
+        assert_eq!(score2(&input)?, 1840311528);
         Ok(())
     }
 }
@@ -1,33 +1,212 @@
-type Counts = [usize; 9];
+use anyhow::anyhow;
 
-fn parse_input(input: &str) -> anyhow::Result<Counts> {
-    let fish: Vec<u8> = input
+/// How a species of fish reproduces: a parent's timer resets to `reset`
+/// after spawning, and each of its `offspring` newborns starts at `newborn`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Lifecycle {
+    reset: usize,
+    newborn: usize,
+    offspring: usize,
+}
+impl Default for Lifecycle {
+    fn default() -> Self {
+        Lifecycle {
+            reset: 6,
+            newborn: 8,
+            offspring: 1,
+        }
+    }
+}
+impl Lifecycle {
+    fn states(&self) -> usize {
+        self.reset.max(self.newborn) + 1
+    }
+
+    /// The per-day growth factor the population converges to, i.e. the dominant
+    /// eigenvalue of the transition. Spawns follow `x(t) = x(t - reset - 1) +
+    /// offspring * x(t - newborn - 1)`, so it is the unique positive root of
+    /// `1 = g^-(reset + 1) + offspring * g^-(newborn + 1)`, which lies in `[1, 1 + offspring]`.
+    fn growth_rate(&self) -> f64 {
+        let excess = |g: f64| {
+            g.powi(-(self.reset as i32 + 1))
+                + self.offspring as f64 * g.powi(-(self.newborn as i32 + 1))
+                - 1.0
+        };
+        let (mut lo, mut hi) = (1.0, 1.0 + self.offspring as f64);
+        for _ in 0..100 {
+            let mid = (lo + hi) / 2.0;
+            if excess(mid) > 0.0 {
+                lo = mid;
+            } else {
+                hi = mid;
+            }
+        }
+        lo
+    }
+
+    fn doubling_time(&self) -> Option<f64> {
+        let g = self.growth_rate();
+        if g > 1.0 {
+            Some(std::f64::consts::LN_2 / g.ln())
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct Population {
+    life: Lifecycle,
+    counts: Vec<usize>,
+}
+impl Population {
+    fn new(life: Lifecycle, fish: &[usize]) -> anyhow::Result<Population> {
+        let mut counts = vec![0; life.states()];
+        for &f in fish {
+            *counts
+                .get_mut(f)
+                .ok_or_else(|| anyhow!("timer {} out of range for {:?}", f, life))? += 1;
+        }
+        Ok(Population { life, counts })
+    }
+
+    fn step(&mut self) {
+        let spawning = self.counts[0];
+        self.counts.rotate_left(1);
+        *self.counts.last_mut().unwrap() = 0;
+        self.counts[self.life.reset] += spawning;
+        self.counts[self.life.newborn] += self.life.offspring * spawning;
+    }
+
+    fn total(&self) -> usize {
+        self.counts.iter().sum()
+    }
+
+    /// Every day of the population's trajectory, starting with day 0.
+    fn history(&self) -> History {
+        History {
+            day: 0,
+            cur: self.clone(),
+        }
+    }
+}
+
+struct History {
+    day: u32,
+    cur: Population,
+}
+impl Iterator for History {
+    type Item = Snapshot;
+
+    fn next(&mut self) -> Option<Snapshot> {
+        if self.day > 0 {
+            self.cur.step();
+        }
+        let snapshot = Snapshot {
+            day: self.day,
+            total: self.cur.total(),
+            counts: self.cur.counts.clone(),
+        };
+        self.day += 1;
+        Some(snapshot)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Snapshot {
+    day: u32,
+    total: usize,
+    counts: Vec<usize>,
+}
+
+fn to_csv(history: impl IntoIterator<Item = Snapshot>) -> String {
+    let mut out = String::new();
+    for (idx, snapshot) in history.into_iter().enumerate() {
+        if idx == 0 {
+            let timers: Vec<String> = (0..snapshot.counts.len())
+                .map(|t| format!("t{}", t))
+                .collect();
+            out += &format!("day,total,{}\n", timers.join(","));
+        }
+        let counts: Vec<String> = snapshot.counts.iter().map(|c| c.to_string()).collect();
+        out += &format!("{},{},{}\n", snapshot.day, snapshot.total, counts.join(","));
+    }
+    out
+}
+
+fn to_json(history: impl IntoIterator<Item = Snapshot>) -> String {
+    let days: Vec<String> = history
+        .into_iter()
+        .map(|snapshot| {
+            let counts: Vec<String> = snapshot.counts.iter().map(|c| c.to_string()).collect();
+            format!(
+                r#"{{"day":{},"total":{},"counts":[{}]}}"#,
+                snapshot.day,
+                snapshot.total,
+                counts.join(",")
+            )
+        })
+        .collect();
+    format!("[{}]", days.join(","))
+}
+
+#[derive(Clone, Debug)]
+struct Species {
+    name: String,
+    population: Population,
+}
+
+fn parse_input(input: &str) -> anyhow::Result<Population> {
+    Population::new(Lifecycle::default(), &parse_timers(input)?)
+}
+fn parse_timers(input: &str) -> anyhow::Result<Vec<usize>> {
+    input
         .trim()
         .split(',')
-        .map(|w| Ok(w.parse::<u8>()?))
-        .collect::<anyhow::Result<_>>()?;
-    let mut counts = Counts::default();
-    for f in fish {
-        counts[f as usize] += 1;
-    }
-    Ok(counts)
+        .map(|w| Ok(w.trim().parse::<usize>()?))
+        .collect()
+}
+
+/// Parses one species per line, as `<name> <reset> <newborn> <offspring>: <timers>`.
+fn parse_species(input: &str) -> anyhow::Result<Vec<Species>> {
+    input
+        .trim()
+        .lines()
+        .map(|line| {
+            let (header, timers) = line
+                .trim()
+                .split_once(':')
+                .ok_or_else(|| anyhow!("invalid species: {}", line))?;
+            let words: Vec<&str> = header.split_whitespace().collect();
+            let (name, reset, newborn, offspring) = match words[..] {
+                [name, reset, newborn, offspring] => (name, reset, newborn, offspring),
+                _ => return Err(anyhow!("invalid species header: {}", header)),
+            };
+            let life = Lifecycle {
+                reset: reset.parse()?,
+                newborn: newborn.parse()?,
+                offspring: offspring.parse()?,
+            };
+            Ok(Species {
+                name: name.to_owned(),
+                population: Population::new(life, &parse_timers(timers)?)?,
+            })
+        })
+        .collect()
 }
 
-fn solve1(mut cur: Counts, days: u32) -> usize {
+fn solve1(mut cur: Population, days: u32) -> usize {
     for _ in 0..days {
-        let mut tmp = Counts::default();
-        tmp[8] = cur[0];
-        tmp[7] = cur[8];
-        tmp[6] = cur[7] + cur[0];
-        tmp[5] = cur[6];
-        tmp[4] = cur[5];
-        tmp[3] = cur[4];
-        tmp[2] = cur[3];
-        tmp[1] = cur[2];
-        tmp[0] = cur[1];
-        std::mem::swap(&mut cur, &mut tmp);
+        cur.step();
     }
-    cur.iter().sum()
+    cur.total()
+}
+
+fn solve_species(species: &[Species], days: u32) -> usize {
+    species
+        .iter()
+        .map(|s| solve1(s.population.clone(), days))
+        .sum()
 }
 
 #[cfg(test)]
@@ -47,11 +226,65 @@ mod test {
         Ok(())
     }
 
+    #[test]
+    fn species() -> anyhow::Result<()> {
+        let input = parse_species(
+            r"
+            lanternfish 6 8 1: 3,4,3,1,2
+            doubler 2 2 2: 0
+            ",
+        )?;
+        assert_eq!(input[0].name, "lanternfish");
+        // The doubler triples every three days: 0 -> 2,2,2 -> ...
+        assert_eq!(solve_species(&input[1..], 3), 3);
+        assert_eq!(solve_species(&input[1..], 6), 9);
+        assert_eq!(solve_species(&input, 18), 26 + 3usize.pow(6));
+        assert!(parse_species("bad 1 1 1: 5").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn history() -> anyhow::Result<()> {
+        let input = parse_input(SMALL)?;
+        let history: Vec<Snapshot> = input.history().take(3).collect();
+        assert_eq!(history[0].counts, vec![0, 1, 1, 2, 1, 0, 0, 0, 0]);
+        assert_eq!(history[2].day, 2);
+        assert_eq!(history[2].total, 6);
+        assert_eq!(
+            input.history().nth(80).map(|s| s.total),
+            Some(solve1(input.clone(), 80))
+        );
+
+        let csv = to_csv(history.clone());
+        assert_eq!(
+            csv.lines().take(2).collect::<Vec<_>>(),
+            vec![
+                "day,total,t0,t1,t2,t3,t4,t5,t6,t7,t8",
+                "0,5,0,1,1,2,1,0,0,0,0"
+            ]
+        );
+        assert!(
+            to_json(history).starts_with(r#"[{"day":0,"total":5,"counts":[0,1,1,2,1,0,0,0,0]},"#)
+        );
+
+        let life = Lifecycle::default();
+        let observed = (solve1(input.clone(), 256) as f64 / solve1(input.clone(), 128) as f64)
+            .powf(1.0 / 128.0);
+        assert!((life.growth_rate() - observed).abs() < 1e-3);
+        assert!((life.doubling_time().unwrap() - 7.96).abs() < 0.01);
+        let sterile = Lifecycle {
+            offspring: 0,
+            ..life
+        };
+        assert_eq!(sterile.doubling_time(), None);
+        Ok(())
+    }
+
     #[test]
     fn normal() -> anyhow::Result<()> {
         let raw = std::fs::read_to_string("data/day06.input")?;
         let input = parse_input(&raw)?;
-        assert_eq!(solve1(input, 80), 396210);
+        assert_eq!(solve1(input.clone(), 80), 396210);
         assert_eq!(solve1(input.clone(), 256), 1770823541496);
         Ok(())
     }
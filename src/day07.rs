@@ -1,3 +1,7 @@
+use std::str::FromStr;
+
+use anyhow::anyhow;
+
 fn parse_input(input: &str) -> anyhow::Result<Vec<i32>> {
     input
         .trim()
@@ -6,26 +10,434 @@ fn parse_input(input: &str) -> anyhow::Result<Vec<i32>> {
         .collect()
 }
 
-fn solve1(input: &[i32]) -> i32 {
-    let eval = |avg: i32| -> i32 { input.iter().map(|x| i32::abs(x - avg)).sum() };
-    let lo = *input.iter().min().unwrap();
-    let hi = *input.iter().max().unwrap();
-    (lo..=hi).map(eval).min().unwrap()
+fn solve1(input: &[i32]) -> anyhow::Result<i64> {
+    Ok(align_exhaustive(input, linear_fuel)?.fuel)
+}
+
+fn solve2(input: &[i32]) -> anyhow::Result<i64> {
+    Ok(align_exhaustive(input, triangular_fuel)?.fuel)
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+struct Alignment {
+    position: i64,
+    fuel: i64,
+}
+
+/// Sums per-crab costs, failing instead of wrapping if any step overflows.
+fn checked_fuel(
+    input: &[i32],
+    pos: i64,
+    cost: impl Fn(usize, i64) -> Option<i64>,
+) -> anyhow::Result<i64> {
+    input
+        .iter()
+        .enumerate()
+        .try_fold(0i64, |acc, (crab, &x)| {
+            let d = (x as i64).checked_sub(pos)?.checked_abs()?;
+            acc.checked_add(cost(crab, d)?)
+        })
+        .ok_or_else(|| anyhow!("fuel overflows at position {}", pos))
+}
+
+fn linear_fuel(input: &[i32], pos: i64) -> anyhow::Result<i64> {
+    checked_fuel(input, pos, |_, d| Some(d))
+}
+
+fn triangular_fuel(input: &[i32], pos: i64) -> anyhow::Result<i64> {
+    checked_fuel(input, pos, |_, d| Some(d.checked_mul(d + 1)? / 2))
+}
+
+fn align_exhaustive(
+    input: &[i32],
+    eval: impl Fn(&[i32], i64) -> anyhow::Result<i64>,
+) -> anyhow::Result<Alignment> {
+    let lo = *input.iter().min().unwrap() as i64;
+    let hi = *input.iter().max().unwrap() as i64;
+    best_of(lo..=hi, |pos| eval(input, pos))
+}
+
+/// Ranks a fuel total for comparison, counting overflow as infinitely expensive.
+fn rank(fuel: anyhow::Result<i64>) -> i128 {
+    fuel.map_or(i128::MAX, i128::from)
+}
+
+/// The cheapest of `positions`. Positions whose fuel overflows lose to any
+/// other, and it's only an error if the cheapest position overflows too.
+fn best_of(
+    positions: impl IntoIterator<Item = i64>,
+    eval: impl Fn(i64) -> anyhow::Result<i64>,
+) -> anyhow::Result<Alignment> {
+    let (position, _) = positions
+        .into_iter()
+        .map(|pos| (pos, rank(eval(pos))))
+        .min_by_key(|&(_, fuel)| fuel)
+        .ok_or_else(|| anyhow!("no positions to align at"))?;
+    Ok(Alignment {
+        position,
+        fuel: eval(position)?,
+    })
+}
+
+/// Any median minimizes the sum of absolute distances.
+fn align_linear(input: &[i32]) -> anyhow::Result<Alignment> {
+    let mut sorted = input.to_vec();
+    sorted.sort_unstable();
+    let position = sorted[(sorted.len() - 1) / 2] as i64;
+    Ok(Alignment {
+        position,
+        fuel: linear_fuel(input, position)?,
+    })
+}
+
+/// The real-valued optimum of the triangular cost is within 1/2 of the mean,
+/// so the integer optimum is one of the few positions surrounding it.
+fn align_triangular(input: &[i32]) -> anyhow::Result<Alignment> {
+    let sum: i64 = input.iter().map(|&x| x as i64).sum();
+    let mean = sum.div_euclid(input.len() as i64);
+    best_of(mean - 1..=mean + 2, |pos| triangular_fuel(input, pos))
+}
+
+/// Finds the minimum of a convex `eval` over `lo..=hi` in O(log(hi - lo))
+/// evaluations by binary searching for the point where the cost stops falling.
+/// Overflowing positions rank as infinitely expensive, as in `best_of`. The
+/// positions that fit form one interval, so when both probes overflow the
+/// search heads toward the last position seen to fit; if none has been yet,
+/// it can miss a narrow interval and report the overflow.
+fn convex_search(
+    mut lo: i64,
+    mut hi: i64,
+    eval: impl Fn(i64) -> anyhow::Result<i64>,
+) -> anyhow::Result<Alignment> {
+    let mut fits = None;
+    while lo < hi {
+        let mid = lo + (hi - lo) / 2;
+        let (here, next) = (rank(eval(mid)), rank(eval(mid + 1)));
+        if next < i128::MAX {
+            fits = Some(mid + 1);
+        } else if here < i128::MAX {
+            fits = Some(mid);
+        }
+        let falling = match fits {
+            Some(pos) if here == i128::MAX && next == i128::MAX => pos > mid,
+            _ => here > next,
+        };
+        if falling {
+            lo = mid + 1;
+        } else {
+            hi = mid;
+        }
+    }
+    Ok(Alignment {
+        position: lo,
+        fuel: eval(lo)?,
+    })
+}
+
+fn align_convex(
+    input: &[i32],
+    eval: impl Fn(&[i32], i64) -> anyhow::Result<i64>,
+) -> anyhow::Result<Alignment> {
+    let lo = *input.iter().min().unwrap() as i64;
+    let hi = *input.iter().max().unwrap() as i64;
+    convex_search(lo, hi, |pos| eval(input, pos))
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum FuelCost {
+    Linear,
+    Triangular,
+    Quadratic,
+    /// Scales `base` by a weight per crab, in input order.
+    Weighted {
+        weights: Vec<i64>,
+        base: Box<FuelCost>,
+    },
+    /// `c[0] + c[1] * d + c[2] * d^2 + ...` for a distance `d`.
+    Polynomial(Vec<i64>),
+}
+impl FuelCost {
+    /// The fuel `crab` spends to move `d` steps, or `None` on overflow.
+    fn cost(&self, crab: usize, d: i64) -> Option<i64> {
+        match self {
+            FuelCost::Linear => Some(d),
+            FuelCost::Triangular => Some(d.checked_mul(d + 1)? / 2),
+            FuelCost::Quadratic => d.checked_mul(d),
+            FuelCost::Weighted { weights, base } => weights[crab].checked_mul(base.cost(crab, d)?),
+            FuelCost::Polynomial(coeffs) => coeffs
+                .iter()
+                .rev()
+                .try_fold(0i64, |acc, &c| acc.checked_mul(d)?.checked_add(c)),
+        }
+    }
+
+    fn total(&self, input: &[i32], pos: i64) -> anyhow::Result<i64> {
+        checked_fuel(input, pos, |crab, d| self.cost(crab, d))
+    }
+
+    /// Checks that every weight list, nested ones included, has one weight per crab.
+    fn check_weights(&self, crabs: usize) -> anyhow::Result<()> {
+        if let FuelCost::Weighted { weights, base } = self {
+            if weights.len() != crabs {
+                return Err(anyhow!(
+                    "{} weights given for {} crabs",
+                    weights.len(),
+                    crabs
+                ));
+            }
+            base.check_weights(crabs)?;
+        }
+        Ok(())
+    }
+
+    /// Whether the total fuel is convex in the meeting position. A cost that is
+    /// convex and non-decreasing in the distance is convex in the position.
+    fn is_convex(&self) -> bool {
+        match self {
+            FuelCost::Linear | FuelCost::Triangular | FuelCost::Quadratic => true,
+            FuelCost::Weighted { weights, base } => {
+                weights.iter().all(|&w| w >= 0) && base.is_convex()
+            }
+            FuelCost::Polynomial(coeffs) => coeffs.iter().skip(1).all(|&c| c >= 0),
+        }
+    }
+}
+impl FromStr for FuelCost {
+    type Err = anyhow::Error;
+
+    /// Accepts `linear`, `triangular`, `quadratic`, `poly:<c0>,<c1>,...` and
+    /// `weighted:<w0>,<w1>,...:<base>`.
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        let parse_list = |list: &str| -> anyhow::Result<Vec<i64>> {
+            list.split(',')
+                .map(|w| Ok(w.trim().parse::<i64>()?))
+                .collect()
+        };
+        let cost = match s.trim().split_once(':') {
+            None => match s.trim() {
+                "linear" => FuelCost::Linear,
+                "triangular" => FuelCost::Triangular,
+                "quadratic" => FuelCost::Quadratic,
+                _ => return Err(anyhow!("invalid fuel cost: {}", s)),
+            },
+            Some(("poly", coeffs)) => FuelCost::Polynomial(parse_list(coeffs)?),
+            Some(("weighted", rest)) => {
+                let (weights, base) = rest
+                    .split_once(':')
+                    .ok_or_else(|| anyhow!("weighted cost needs a base: {}", s))?;
+                FuelCost::Weighted {
+                    weights: parse_list(weights)?,
+                    base: Box::new(base.parse()?),
+                }
+            }
+            Some(_) => return Err(anyhow!("invalid fuel cost: {}", s)),
+        };
+        Ok(cost)
+    }
+}
+
+/// Picks the cheapest applicable optimizer: a closed form where one is known,
+/// a convex search when the cost is convex, and every position otherwise.
+fn align(input: &[i32], cost: &FuelCost) -> anyhow::Result<Alignment> {
+    cost.check_weights(input.len())?;
+    match cost {
+        FuelCost::Linear => align_linear(input),
+        FuelCost::Triangular => align_triangular(input),
+        _ if cost.is_convex() => align_convex(input, |input, pos| cost.total(input, pos)),
+        _ => align_exhaustive(input, |input, pos| cost.total(input, pos)),
+    }
+}
+
+/// Parses one point per line, e.g. `x,y` or `x,y,z`; every point must have
+/// the same number of coordinates.
+fn parse_points(input: &str) -> anyhow::Result<Vec<Vec<i64>>> {
+    let points: Vec<Vec<i64>> = input
+        .trim()
+        .lines()
+        .map(|line| {
+            line.trim()
+                .split(',')
+                .map(|w| Ok(w.trim().parse::<i64>()?))
+                .collect()
+        })
+        .collect::<anyhow::Result<_>>()?;
+    let dims = points.first().map_or(0, |p| p.len());
+    if let Some(p) = points.iter().find(|p| p.len() != dims) {
+        return Err(anyhow!("expected {} coordinates, got {:?}", dims, p));
+    }
+    Ok(points)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Metric {
+    Manhattan,
+    Chebyshev,
+    Euclidean,
+}
+impl Metric {
+    fn distance(&self, a: &[f64], b: &[i64]) -> f64 {
+        let deltas = a.iter().zip(b).map(|(&x, &y)| (x - y as f64).abs());
+        match self {
+            Metric::Manhattan => deltas.sum(),
+            Metric::Chebyshev => deltas.fold(0.0, f64::max),
+            Metric::Euclidean => deltas.map(|d| d * d).sum::<f64>().sqrt(),
+        }
+    }
+
+    fn total(&self, at: &[f64], points: &[Vec<i64>]) -> f64 {
+        points.iter().map(|p| self.distance(at, p)).sum()
+    }
+}
+impl FromStr for Metric {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        let metric = match s {
+            "manhattan" => Metric::Manhattan,
+            "chebyshev" => Metric::Chebyshev,
+            "euclidean" => Metric::Euclidean,
+            _ => return Err(anyhow!("invalid metric: {}", s)),
+        };
+        Ok(metric)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Meeting {
+    point: Vec<f64>,
+    fuel: f64,
 }
 
-fn solve2(input: &[i32]) -> i32 {
-    let eval = |avg: i32| -> i32 {
-        input
+/// Finds a meeting point minimizing total distance under `metric`:
+///   - Manhattan separates per axis, so the coordinate-wise median is exact.
+///   - Chebyshev in 2D is Manhattan rotated by 45 degrees (and scaled by 1/2), so it
+///     is exact as well; in higher dimensions we fall back to a pattern search.
+///   - Euclidean is the geometric median, approximated with Weiszfeld's algorithm.
+fn meet(points: &[Vec<i64>], metric: Metric) -> anyhow::Result<Meeting> {
+    if points.is_empty() {
+        return Err(anyhow!("no points to meet at"));
+    }
+    let point = match (metric, points[0].len()) {
+        (Metric::Manhattan, _) | (Metric::Chebyshev, 1) => median_point(points),
+        (Metric::Chebyshev, 2) => {
+            let rotated: Vec<Vec<i64>> = points
+                .iter()
+                .map(|p| vec![p[0] + p[1], p[0] - p[1]])
+                .collect();
+            let m = median_point(&rotated);
+            vec![(m[0] + m[1]) / 2.0, (m[0] - m[1]) / 2.0]
+        }
+        (Metric::Chebyshev, _) => {
+            pattern_search(median_point(points), |at| metric.total(at, points))
+        }
+        (Metric::Euclidean, _) => weiszfeld(points),
+    };
+    Ok(Meeting {
+        fuel: metric.total(&point, points),
+        point,
+    })
+}
+
+fn median_point(points: &[Vec<i64>]) -> Vec<f64> {
+    (0..points[0].len())
+        .map(|axis| {
+            let mut values: Vec<i64> = points.iter().map(|p| p[axis]).collect();
+            values.sort_unstable();
+            values[(values.len() - 1) / 2] as f64
+        })
+        .collect()
+}
+
+/// Moves to the best of the 3^d - 1 neighbours at the current step size while
+/// that improves the cost, halving the step otherwise. Converges on convex
+/// costs, including non-smooth ones where single-axis moves would get stuck.
+fn pattern_search(mut at: Vec<f64>, eval: impl Fn(&[f64]) -> f64) -> Vec<f64> {
+    let dims = at.len();
+    let directions: Vec<Vec<f64>> = (0..3usize.pow(dims as u32))
+        .map(|mut n| {
+            (0..dims)
+                .map(|_| {
+                    let d = (n % 3) as f64 - 1.0;
+                    n /= 3;
+                    d
+                })
+                .collect::<Vec<f64>>()
+        })
+        .filter(|d| d.iter().any(|&x| x != 0.0))
+        .collect();
+    let mut best = eval(&at);
+    let mut step = at.iter().fold(1.0, |acc: f64, x| acc.max(x.abs()));
+    while step > 1e-9 {
+        let candidate = directions
             .iter()
-            .map(|x| {
-                let d = i32::abs(x - avg);
-                d * (d + 1) / 2
+            .map(|d| {
+                at.iter()
+                    .zip(d)
+                    .map(|(x, dx)| x + step * dx)
+                    .collect::<Vec<f64>>()
             })
-            .sum()
-    };
-    let lo = *input.iter().min().unwrap();
-    let hi = *input.iter().max().unwrap();
-    (lo..=hi).map(eval).min().unwrap()
+            .map(|p| (eval(&p), p))
+            .min_by(|a, b| a.0.total_cmp(&b.0))
+            .unwrap();
+        if candidate.0 < best {
+            best = candidate.0;
+            at = candidate.1;
+        } else {
+            step /= 2.0;
+        }
+    }
+    at
+}
+
+/// Weiszfeld's algorithm: repeatedly move to the average of the points weighted
+/// by their inverse distance. Plain Weiszfeld is undefined on an input point, so
+/// this uses the Vardi-Zhang modification there: a point shared by `k` inputs
+/// is optimal iff the unit vectors towards all the others sum to at most `k`,
+/// and otherwise the step is damped between that point and the Weiszfeld average.
+fn weiszfeld(points: &[Vec<i64>]) -> Vec<f64> {
+    let dims = points[0].len();
+    let mut at: Vec<f64> = (0..dims)
+        .map(|axis| points.iter().map(|p| p[axis] as f64).sum::<f64>() / points.len() as f64)
+        .collect();
+    for _ in 0..10_000 {
+        let mut numer = vec![0.0; dims];
+        let mut pull = vec![0.0; dims];
+        let mut denom = 0.0;
+        let mut here = 0.0;
+        for p in points {
+            let d = Metric::Euclidean.distance(&at, p);
+            if d < 1e-12 {
+                here += 1.0;
+                continue;
+            }
+            for axis in 0..dims {
+                numer[axis] += p[axis] as f64 / d;
+                pull[axis] += (p[axis] as f64 - at[axis]) / d;
+            }
+            denom += 1.0 / d;
+        }
+        let pull = pull.iter().map(|x| x * x).sum::<f64>().sqrt();
+        if denom == 0.0 || pull <= here {
+            return at;
+        }
+        let damping = here / pull;
+        let next: Vec<f64> = numer
+            .iter()
+            .zip(&at)
+            .map(|(n, a)| (1.0 - damping) * (n / denom) + damping * a)
+            .collect();
+        let shift: f64 = at
+            .iter()
+            .zip(&next)
+            .map(|(a, b)| (a - b) * (a - b))
+            .sum::<f64>()
+            .sqrt();
+        at = next;
+        if shift < 1e-12 {
+            break;
+        }
+    }
+    at
 }
 
 #[cfg(test)]
@@ -39,8 +451,176 @@ mod test {
     #[test]
     fn small() -> anyhow::Result<()> {
         let input = parse_input(SMALL)?;
-        assert_eq!(solve1(&input), 37);
-        assert_eq!(solve2(&input), 168);
+        assert_eq!(solve1(&input)?, 37);
+        assert_eq!(solve2(&input)?, 168);
+        assert_eq!(
+            align_linear(&input)?,
+            Alignment {
+                position: 2,
+                fuel: 37
+            }
+        );
+        assert_eq!(
+            align_triangular(&input)?,
+            Alignment {
+                position: 5,
+                fuel: 168
+            }
+        );
+        assert_eq!(align_convex(&input, linear_fuel)?, align_linear(&input)?);
+        assert_eq!(
+            align_convex(&input, triangular_fuel)?,
+            align_triangular(&input)?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn costs() -> anyhow::Result<()> {
+        let input = parse_input(SMALL)?;
+        let fuel = |cost: &str| -> anyhow::Result<i64> { Ok(align(&input, &cost.parse()?)?.fuel) };
+        assert_eq!(fuel("linear")?, 37);
+        assert_eq!(fuel("triangular")?, 168);
+        assert_eq!(fuel("poly:0,1")?, 37);
+        assert_eq!(fuel("poly:0,1,1")?, 2 * 168);
+        assert_eq!(fuel("quadratic")?, fuel("poly:0,0,1")?);
+        assert_eq!(fuel("weighted:1,1,1,1,1,1,1,1,1,1:triangular")?, 168);
+        assert_eq!(
+            align(&input, &"weighted:1,0,0,0,0,0,0,0,0,0:linear".parse()?)?,
+            Alignment {
+                position: 16,
+                fuel: 0
+            }
+        );
+
+        // Cheapest to be exactly 2 away from everyone; not convex.
+        let cost: FuelCost = "poly:4,-4,1".parse()?;
+        assert!(!cost.is_convex());
+        assert_eq!(align(&[0, 4], &cost)?.position, 2);
+
+        assert!("weighted:1,2:linear".parse::<FuelCost>().is_ok());
+        assert!(align(&input, &"weighted:1,2:linear".parse()?).is_err());
+        assert!(align(&[1, 2], &"weighted:1,1:weighted:1:linear".parse()?).is_err());
+        assert_eq!(
+            align(&[0, 10], &"weighted:1,1:weighted:2,3:linear".parse()?)?,
+            Alignment {
+                position: 10,
+                fuel: 20
+            }
+        );
+        assert!("cubic".parse::<FuelCost>().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn points() -> anyhow::Result<()> {
+        let square = parse_points(
+            r"
+            0,0
+            2,0
+            0,2
+            2,2
+            ",
+        )?;
+        let euclid = meet(&square, "euclidean".parse()?)?;
+        assert!(euclid.point.iter().all(|x| (x - 1.0).abs() < 1e-6));
+        assert!((euclid.fuel - 4.0 * 2f64.sqrt()).abs() < 1e-6);
+
+        // The search starts at the mean, which is an input point here but not
+        // the optimum.
+        let pulled = parse_points("-9,0\n0,0\n3,0\n3,0\n3,0")?;
+        let euclid = meet(&pulled, Metric::Euclidean)?;
+        assert!((euclid.point[0] - 3.0).abs() < 1e-6 && euclid.point[1].abs() < 1e-6);
+        assert!((euclid.fuel - 15.0).abs() < 1e-6);
+        // Here the mean is an input point and the optimum.
+        let cross = parse_points("0,0\n1,0\n-1,0\n0,1\n0,-1")?;
+        assert_eq!(meet(&cross, Metric::Euclidean)?.point, vec![0.0, 0.0]);
+
+        let line = parse_points("1\n2\n3\n100")?;
+        assert_eq!(meet(&line, Metric::Manhattan)?.fuel, 100.0);
+        assert_eq!(meet(&line, Metric::Chebyshev)?.fuel, 100.0);
+
+        let scattered = parse_points(
+            r"
+            0,0,0
+            10,1,2
+            3,9,4
+            7,7,7
+            -5,2,8
+            ",
+        )?;
+        let manhattan = meet(&scattered, Metric::Manhattan)?;
+        assert_eq!(manhattan.point, vec![3.0, 2.0, 4.0]);
+        assert_eq!(manhattan.fuel, 50.0);
+
+        // The continuous optimum is at least as good as any lattice point.
+        let lattice = |metric: Metric| {
+            let mut best = f64::MAX;
+            for x in -5..=10 {
+                for y in 0..=9 {
+                    for z in 0..=8 {
+                        best = best.min(metric.total(&[x as f64, y as f64, z as f64], &scattered));
+                    }
+                }
+            }
+            best
+        };
+        for metric in [Metric::Chebyshev, Metric::Euclidean] {
+            assert!(meet(&scattered, metric)?.fuel <= lattice(metric) + 1e-6);
+        }
+        let flat: Vec<Vec<i64>> = scattered.iter().map(|p| p[..2].to_vec()).collect();
+        let chebyshev = meet(&flat, Metric::Chebyshev)?;
+        let searched = pattern_search(vec![0.0, 0.0], |at| Metric::Chebyshev.total(at, &flat));
+        assert!(chebyshev.fuel <= Metric::Chebyshev.total(&searched, &flat) + 1e-6);
+
+        assert!(parse_points("1,2\n3").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn wide() -> anyhow::Result<()> {
+        let input = [-1_000_000_000, 0, 3, 1_000_000_000, 999_999_999];
+        assert_eq!(
+            align_linear(&input)?,
+            Alignment {
+                position: 3,
+                fuel: 2_999_999_999
+            }
+        );
+        assert_eq!(
+            align_triangular(&input)?,
+            align_convex(&input, triangular_fuel)?
+        );
+
+        // Distances of ~4e9 square to more than i64 can hold, but only away
+        // from the middle, where the optimum is.
+        let input = [i32::MIN, i32::MAX];
+        assert!(align_linear(&input).is_ok());
+        assert!(align_triangular(&input).is_ok());
+        assert!(FuelCost::Quadratic.total(&input, i32::MIN as i64).is_err());
+        assert_eq!(
+            align(&input, &FuelCost::Quadratic)?,
+            Alignment {
+                position: -1,
+                fuel: i64::MAX - (1 << 32) + 2
+            }
+        );
+        assert_eq!(
+            align(&[-2_000_000_000, 0, 2_000_000_000], &FuelCost::Quadratic)?,
+            Alignment {
+                position: 0,
+                fuel: 8_000_000_000_000_000_000
+            }
+        );
+        assert_eq!(
+            best_of(input.iter().map(|&x| x as i64), |pos| {
+                FuelCost::Quadratic.total(&input, pos)
+            })
+            .map_err(|e| e.to_string()),
+            Err(format!("fuel overflows at position {}", i32::MIN))
+        );
+        // Even the best position needs ~2^94 fuel.
+        assert!(align(&input, &"poly:0,0,0,1".parse()?).is_err());
         Ok(())
     }
 
@@ -48,8 +628,10 @@ mod test {
     fn normal() -> anyhow::Result<()> {
         let raw = std::fs::read_to_string("data/day07.input")?;
         let input = parse_input(&raw)?;
-        assert_eq!(solve1(&input), 344297);
-        assert_eq!(solve2(&input), 97164301);
+        assert_eq!(solve1(&input)?, 344297);
+        assert_eq!(solve2(&input)?, 97164301);
+        assert_eq!(align_linear(&input)?.fuel, 344297);
+        assert_eq!(align_triangular(&input)?.fuel, 97164301);
         Ok(())
     }
 }
@@ -1,3 +1,5 @@
+use std::str::FromStr;
+
 use anyhow::anyhow;
 use nom::{
     bytes::complete::tag,
@@ -52,64 +54,751 @@ fn solve1(input: &[Sample]) -> usize {
         .count()
 }
 
-fn solve2(input: &[Sample]) -> usize {
-    // Basically, we need to figure out what the mapping translation is between
-    // the expected "abcdefg" and whatever is actually being pushed out.
-    // We can quickly do some narrowing down:
-    //   - any two letter signals directly indicate what "cf" maps to
-    //   - any three letter signals tell us "acf"
-    //   - any four letter signals tell us "bcdf"
-    // One observation: if we get entirely the same sequence of 5-letter signals,
-    // there's nothing we can do. So this is not solvable in all scenarios.
-    // This approach tries a simple heuristic and panics if that doesn't work.
+fn solve2(input: &[Sample]) -> anyhow::Result<usize> {
+    input.iter().map(translated_sum).sum()
+}
 
-    input
+fn translated_sum(sample: &Sample) -> anyhow::Result<usize> {
+    decode_sample(sample, &Alphabet::seven())?.value()
+}
+
+/// The glyphs a display can show, with bit `i` of each glyph standing for
+/// segment `'a' + i`. Wires are named the same way.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Alphabet {
+    segments: usize,
+    glyphs: Vec<(char, u32)>,
+}
+impl Alphabet {
+    fn new(segments: usize, glyphs: Vec<(char, u32)>) -> anyhow::Result<Alphabet> {
+        if segments > 26 {
+            return Err(anyhow!(
+                "at most 26 segments are supported, got {}",
+                segments
+            ));
+        }
+        for (idx, &(c, g)) in glyphs.iter().enumerate() {
+            if g >> segments != 0 {
+                return Err(anyhow!(
+                    "glyph {:?} uses more than {} segments",
+                    c,
+                    segments
+                ));
+            }
+            if glyphs[..idx].iter().any(|&(_, other)| other == g) {
+                return Err(anyhow!("glyph {:?} duplicates another glyph", c));
+            }
+        }
+        Ok(Alphabet { segments, glyphs })
+    }
+
+    /// The standard seven-segment digits.
+    fn seven() -> Alphabet {
+        let digits = [
+            0b1110111, 0b0100100, 0b1011101, 0b1101101, 0b0101110, 0b1101011, 0b1111011, 0b0100101,
+            0b1111111, 0b1101111,
+        ];
+        Alphabet {
+            segments: 7,
+            glyphs: ('0'..='9').zip(digits).collect(),
+        }
+    }
+
+    /// Digits and capital letters on a fourteen-segment display, with segments
+    /// `a`-`f` running clockwise around the edge from the top, `g`/`h` the two
+    /// halves of the middle bar, and `i`-`n` the inner diagonals and verticals.
+    fn fourteen() -> Alphabet {
+        Alphabet {
+            segments: 14,
+            glyphs: FOURTEEN_SEGMENT.to_vec(),
+        }
+    }
+
+    /// The fourteen-segment glyphs on a sixteen-segment display, which also
+    /// splits the top and bottom bars into halves: `a`/`b` on top, `e`/`f` at
+    /// the bottom. Some glyphs use only one half of a bar.
+    fn sixteen() -> Alphabet {
+        // Fourteen-segment bits, in order, and the sixteen-segment bits they become.
+        let split: [u32; 14] = [
+            0b11,
+            0b100,
+            0b1000,
+            0b110000,
+            0b1000000,
+            0b10000000,
+            1 << 8,
+            1 << 9,
+            1 << 10,
+            1 << 11,
+            1 << 12,
+            1 << 13,
+            1 << 14,
+            1 << 15,
+        ];
+        let glyphs = FOURTEEN_SEGMENT
+            .iter()
+            .map(|&(c, g)| {
+                let wide = (0..14)
+                    .filter(|b| g & 1 << b != 0)
+                    .fold(0, |acc, b| acc | split[b]);
+                let wide = match c {
+                    // A flag on the top left, and a short foot.
+                    '1' => wide | 0b1,
+                    'L' => wide & !0b10000,
+                    _ => wide,
+                };
+                (c, wide)
+            })
+            .collect();
+        Alphabet {
+            segments: 16,
+            glyphs,
+        }
+    }
+
+    fn mask(&self, signal: &[u8]) -> anyhow::Result<u32> {
+        signal.iter().try_fold(0, |acc, &b| {
+            let w = b.wrapping_sub(b'a') as usize;
+            if w < self.segments {
+                Ok(acc | 1 << w)
+            } else {
+                Err(anyhow!("invalid wire {:?}", b as char))
+            }
+        })
+    }
+
+    fn glyph(&self, lit: u32) -> Option<char> {
+        self.glyphs
+            .iter()
+            .find(|&&(_, g)| g == lit)
+            .map(|&(c, _)| c)
+    }
+}
+impl FromStr for Alphabet {
+    type Err = anyhow::Error;
+
+    /// Parses a glyph table such as `0=abcefg 1=cf ...`; the display has as many
+    /// segments as the highest letter used.
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        let glyphs: Vec<(char, u32)> = s
+            .split_whitespace()
+            .map(|entry| {
+                let (c, segments) = entry
+                    .split_once('=')
+                    .ok_or_else(|| anyhow!("invalid glyph: {}", entry))?;
+                let mut chars = c.chars();
+                let c = match (chars.next(), chars.next()) {
+                    (Some(c), None) => c,
+                    _ => return Err(anyhow!("invalid glyph: {}", entry)),
+                };
+                let g = segments.bytes().try_fold(0, |acc, b| match b {
+                    b'a'..=b'z' => Ok(acc | 1 << (b - b'a')),
+                    _ => Err(anyhow!("invalid segment {:?}", b as char)),
+                })?;
+                Ok((c, g))
+            })
+            .collect::<anyhow::Result<_>>()?;
+        let segments = glyphs.iter().map(|&(_, g)| 32 - g.leading_zeros()).max();
+        Alphabet::new(segments.unwrap_or(0) as usize, glyphs)
+    }
+}
+
+const FOURTEEN_SEGMENT: [(char, u32); 36] = [
+    ('0', 0x0C3F),
+    ('1', 0x0006),
+    ('2', 0x00DB),
+    ('3', 0x008F),
+    ('4', 0x00E6),
+    ('5', 0x2069),
+    ('6', 0x00FD),
+    ('7', 0x0007),
+    ('8', 0x00FF),
+    ('9', 0x00EF),
+    ('A', 0x00F7),
+    ('B', 0x128F),
+    ('C', 0x0039),
+    ('D', 0x120F),
+    ('E', 0x00F9),
+    ('F', 0x0071),
+    ('G', 0x00BD),
+    ('H', 0x00F6),
+    ('I', 0x1209),
+    ('J', 0x001E),
+    ('K', 0x2470),
+    ('L', 0x0038),
+    ('M', 0x0536),
+    ('N', 0x2136),
+    ('O', 0x003F),
+    ('P', 0x00F3),
+    ('Q', 0x203F),
+    ('R', 0x20F3),
+    ('S', 0x018D),
+    ('T', 0x1201),
+    ('U', 0x003E),
+    ('V', 0x0C30),
+    ('W', 0x2836),
+    ('X', 0x2D00),
+    ('Y', 0x1500),
+    ('Z', 0x0C09),
+];
+
+/// A sample together with the wiring that explains it and the glyph each
+/// of its signals shows.
+struct Decoded {
+    wiring: Wiring,
+    inputs: Vec<(Signal, char)>,
+    outputs: Vec<(Signal, char)>,
+}
+impl Decoded {
+    fn text(&self) -> String {
+        self.outputs.iter().map(|&(_, c)| c).collect()
+    }
+
+    fn value(&self) -> anyhow::Result<usize> {
+        self.outputs.iter().try_fold(0, |acc, &(_, c)| {
+            let d = c
+                .to_digit(10)
+                .ok_or_else(|| anyhow!("{:?} is not a digit", c))?;
+            Ok(10 * acc + d as usize)
+        })
+    }
+}
+impl std::fmt::Display for Decoded {
+    /// Formats as `a->c, b->f, ... | acedgfb=8 ... | cdfeb=5 ...`.
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let show = |signals: &[(Signal, char)]| -> Vec<String> {
+            signals
+                .iter()
+                .map(|(s, c)| format!("{}={}", String::from_utf8_lossy(s), c))
+                .collect()
+        };
+        write!(
+            f,
+            "{} | {} | {}",
+            self.wiring,
+            show(&self.inputs).join(" "),
+            show(&self.outputs).join(" ")
+        )
+    }
+}
+
+fn decode_sample(sample: &Sample, alphabet: &Alphabet) -> anyhow::Result<Decoded> {
+    let wiring = match decode(sample, alphabet)? {
+        Decoding::Unique(wiring) => wiring,
+        other => return Err(anyhow!("{}", other)),
+    };
+    let translate = |signals: &[Signal]| -> anyhow::Result<Vec<(Signal, char)>> {
+        signals
+            .iter()
+            .map(|s| {
+                let lit = wiring.apply(alphabet.mask(s)?);
+                let c = alphabet
+                    .glyph(lit)
+                    .ok_or_else(|| anyhow!("{:?} does not decode to a glyph", s))?;
+                Ok((s.clone(), c))
+            })
+            .collect()
+    };
+    Ok(Decoded {
+        inputs: translate(&sample.inputs)?,
+        outputs: translate(&sample.outputs)?,
+        wiring,
+    })
+}
+
+/// Quotes `s` as a JSON string.
+fn json_string(s: &str) -> String {
+    let mut out = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => out += "\\\"",
+            '\\' => out += "\\\\",
+            c if c.is_control() => out += &format!("\\u{:04x}", c as u32),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// Reports every sample as a JSON object; samples that fail to decode carry
+/// an `error` instead of a wiring.
+fn json_report(input: &[Sample], alphabet: &Alphabet) -> String {
+    let signals = |signals: &[(Signal, char)]| -> String {
+        let entries: Vec<String> = signals
+            .iter()
+            .map(|(s, c)| {
+                format!(
+                    r#"{{"raw":{},"glyph":{}}}"#,
+                    json_string(&String::from_utf8_lossy(s)),
+                    json_string(&c.to_string())
+                )
+            })
+            .collect();
+        format!("[{}]", entries.join(","))
+    };
+    let lines: Vec<String> = input
         .iter()
-        .map(|sample| translated_sum(sample).unwrap())
-        .sum()
-}
-
-fn translated_sum(sample: &Sample) -> Option<usize> {
-    let one = sample.signals().find(|s| s.len() == 2)?;
-    let four = sample.signals().find(|s| s.len() == 4)?;
-    // I think there is enough info here to get the right answer in most cases without
-    // any wacky permutations.
-    //   - if a signal has 2, 3, 4, or 7 letters, we know it immediately
-    //   - if a signal has 5 letters, it could be [2, 3, 5]
-    //   - if a signal has 6 letters, it could be [0, 6, 9]
-
-    // Consider the [2, 3, 5] case. If we happen to know what letters 1 contains, we can
-    // distinguish 3 from [2, 5] --- 3 contains all of the letters in 1.
-    // Similarly, if we happen to know 4, we can distinguish 2 from 5: 2 overlaps 4 w/ two segments,
-    // while 5 overlaps 4 w/ three segments.
-
-    // Consider the [0, 6, 9] case. Only 9 fully overlaps w/ 4. 0 and 6 differ in how they overlap w/ 1.
-
-    let output = sample.outputs.iter().map(|signal| match signal.len() {
-        2 => 1,
-        3 => 7,
-        4 => 4,
-        7 => 8,
-        // length 5 --> [2, 3, 5]
-        5 => match (overlap(signal, one), overlap(signal, four)) {
-            (2, _) => 3,
-            (_, 3) => 5,
-            (_, 2) => 2,
-            _ => panic!("{:?} overlaps w/ 1 and 4 strangely", signal),
-        },
-        // length 6 --> [0, 6, 9]
-        6 => match (overlap(signal, one), overlap(signal, four)) {
-            (_, 4) => 9,
-            (2, _) => 0,
-            (1, _) => 6,
-            _ => panic!("{:?} overlaps w/ 1 and 4 strangely", signal),
-        },
-        _ => panic!("{:?} has a weird length", signal),
-    });
-    Some(output.fold(0, |acc, d| 10 * acc + d))
+        .enumerate()
+        .map(|(idx, sample)| match decode_sample(sample, alphabet) {
+            Ok(decoded) => {
+                let wiring: Vec<String> = decoded
+                    .wiring
+                    .0
+                    .iter()
+                    .enumerate()
+                    .map(|(w, &seg)| {
+                        format!(
+                            "{}:{}",
+                            json_string(&letter(w).to_string()),
+                            json_string(&letter(seg).to_string())
+                        )
+                    })
+                    .collect();
+                format!(
+                    r#"{{"line":{},"wiring":{{{}}},"inputs":{},"outputs":{},"text":{}}}"#,
+                    idx + 1,
+                    wiring.join(","),
+                    signals(&decoded.inputs),
+                    signals(&decoded.outputs),
+                    json_string(&decoded.text())
+                )
+            }
+            Err(e) => format!(
+                r#"{{"line":{},"error":{}}}"#,
+                idx + 1,
+                json_string(&e.to_string())
+            ),
+        })
+        .collect();
+    format!("[{}]", lines.join(","))
+}
+
+/// Draws seven-segment masks as classic three-row ASCII art, one line per row.
+/// Segments follow the puzzle's layout: `a` on top, `b`/`c` upper left and right,
+/// `d` in the middle, `e`/`f` lower left and right and `g` at the bottom.
+fn render(masks: &[u32]) -> Vec<String> {
+    // The segment (if any) drawn in each cell of a digit, and how to draw it.
+    const LAYOUT: [[Option<(u8, char)>; 3]; 3] = [
+        [None, Some((b'a', '_')), None],
+        [Some((b'b', '|')), Some((b'd', '_')), Some((b'c', '|'))],
+        [Some((b'e', '|')), Some((b'g', '_')), Some((b'f', '|'))],
+    ];
+    LAYOUT
+        .iter()
+        .map(|row| {
+            let cells: Vec<String> = masks
+                .iter()
+                .map(|&mask| {
+                    row.iter()
+                        .map(|cell| match cell {
+                            Some((seg, c)) if mask & 1 << (seg - b'a') != 0 => *c,
+                            _ => ' ',
+                        })
+                        .collect()
+                })
+                .collect();
+            cells.join(" ")
+        })
+        .collect()
+}
+
+/// Shows a sample's outputs as the miswired display lights them, next to
+/// how they should look once the wiring is corrected.
+fn render_sample(sample: &Sample) -> anyhow::Result<String> {
+    let alphabet = Alphabet::seven();
+    let wiring = match decode(sample, &alphabet)? {
+        Decoding::Unique(wiring) => wiring,
+        other => return Err(anyhow!("{}", other)),
+    };
+    let raw: Vec<u32> = sample
+        .outputs
+        .iter()
+        .map(|s| alphabet.mask(s))
+        .collect::<anyhow::Result<_>>()?;
+    let corrected: Vec<u32> = raw.iter().map(|&m| wiring.apply(m)).collect();
+    let (raw, corrected) = (render(&raw), render(&corrected));
+    let width = raw[0].len();
+    let mut out = format!("{:width$}   {}\n", "scrambled", "corrected", width = width);
+    for (r, c) in raw.iter().zip(&corrected) {
+        out += &format!("{}   {}\n", r, c);
+    }
+    Ok(out)
+}
+
+/// The most likely reading of a sample whose signals may have dropped or
+/// gained segments, along with how sure we are of each part of it.
+struct NoisyDecoding {
+    wiring: Wiring,
+    wiring_confidence: f64,
+    outputs: Vec<(char, f64)>,
+}
+impl NoisyDecoding {
+    fn text(&self) -> String {
+        self.outputs.iter().map(|&(c, _)| c).collect()
+    }
+}
+
+/// Decodes a sample assuming every segment of the intended glyph was flipped
+/// (dropped or added) independently with probability `error_rate`, and that
+/// all glyphs and wirings are equally likely a priori.
+///
+/// The wiring is the one with the highest posterior probability. Each output's
+/// glyph and confidence come from its posterior with the wiring marginalized
+/// out, so a sample that doesn't pin down the wiring can still decode reliably.
+/// Every wiring is scored, so this only supports displays of up to 8 segments.
+fn decode_noisy(
+    sample: &Sample,
+    alphabet: &Alphabet,
+    error_rate: f64,
+) -> anyhow::Result<NoisyDecoding> {
+    if alphabet.segments > 8 {
+        return Err(anyhow!(
+            "noisy decoding supports up to 8 segments, got {}",
+            alphabet.segments
+        ));
+    }
+    if !(0.0..1.0).contains(&error_rate) || error_rate == 0.0 {
+        return Err(anyhow!("error rate must be in (0, 1), got {}", error_rate));
+    }
+    let inputs: Vec<u32> = sample
+        .inputs
+        .iter()
+        .map(|s| alphabet.mask(s))
+        .collect::<anyhow::Result<_>>()?;
+    let outputs: Vec<u32> = sample
+        .outputs
+        .iter()
+        .map(|s| alphabet.mask(s))
+        .collect::<anyhow::Result<_>>()?;
+
+    // log P(lit | glyph) for each glyph, given the segments actually lit.
+    let (hit, miss) = ((1.0 - error_rate).ln(), error_rate.ln());
+    let log_likelihoods = |lit: u32| -> Vec<f64> {
+        alphabet
+            .glyphs
+            .iter()
+            .map(|&(_, g)| {
+                let flipped = (lit ^ g).count_ones() as f64;
+                flipped * miss + (alphabet.segments as f64 - flipped) * hit
+            })
+            .collect()
+    };
+
+    let candidates = wirings(alphabet.segments);
+    let scores: Vec<f64> = candidates
+        .iter()
+        .map(|wiring| {
+            inputs
+                .iter()
+                .chain(&outputs)
+                .map(|&s| log_sum_exp(&log_likelihoods(wiring.apply(s))))
+                .sum()
+        })
+        .collect();
+    let total = log_sum_exp(&scores);
+    let posterior: Vec<f64> = scores.iter().map(|s| (s - total).exp()).collect();
+    let best = (0..candidates.len())
+        .max_by(|&a, &b| posterior[a].total_cmp(&posterior[b]))
+        .unwrap();
+
+    let outputs = outputs
+        .iter()
+        .map(|&s| {
+            let mut marginal = vec![0.0; alphabet.glyphs.len()];
+            for (wiring, &p) in candidates.iter().zip(&posterior) {
+                let ll = log_likelihoods(wiring.apply(s));
+                let norm = log_sum_exp(&ll);
+                for (m, l) in marginal.iter_mut().zip(ll) {
+                    *m += p * (l - norm).exp();
+                }
+            }
+            let g = (0..marginal.len())
+                .max_by(|&a, &b| marginal[a].total_cmp(&marginal[b]))
+                .unwrap();
+            (alphabet.glyphs[g].0, marginal[g])
+        })
+        .collect();
+    Ok(NoisyDecoding {
+        wiring: candidates[best].clone(),
+        wiring_confidence: posterior[best],
+        outputs,
+    })
+}
+
+fn log_sum_exp(values: &[f64]) -> f64 {
+    let max = values.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+    max + values.iter().map(|v| (v - max).exp()).sum::<f64>().ln()
+}
+
+/// Produces a sample line, as in the puzzle input, for a display with the
+/// given wiring showing `value` as four digits. The ten unique patterns and
+/// the wires within each pattern are shuffled according to `seed`.
+fn encode(wiring: &Wiring, value: usize, seed: u64) -> anyhow::Result<String> {
+    let alphabet = Alphabet::seven();
+    let mut sorted = wiring.0.clone();
+    sorted.sort_unstable();
+    if !sorted.iter().copied().eq(0..alphabet.segments) {
+        return Err(anyhow!("{} is not a seven-segment wiring", wiring));
+    }
+    if value >= 10_000 {
+        return Err(anyhow!("{} does not fit in four digits", value));
+    }
+    let mut wire_for = vec![0; alphabet.segments];
+    for (w, &seg) in wiring.0.iter().enumerate() {
+        wire_for[seg] = w;
+    }
+    let mut rng = XorShift::new(seed);
+    let mut scramble = |glyph: u32| -> String {
+        let mut wires: Vec<char> = (0..alphabet.segments)
+            .filter(|&seg| glyph & 1 << seg != 0)
+            .map(|seg| letter(wire_for[seg]))
+            .collect();
+        rng.shuffle(&mut wires);
+        wires.into_iter().collect()
+    };
+    let mut inputs: Vec<String> = alphabet.glyphs.iter().map(|&(_, g)| scramble(g)).collect();
+    let outputs: Vec<String> = format!("{:04}", value)
+        .chars()
+        .map(|c| {
+            let &(_, g) = alphabet.glyphs.iter().find(|&&(d, _)| d == c).unwrap();
+            scramble(g)
+        })
+        .collect();
+    rng.shuffle(&mut inputs);
+    Ok(format!("{} | {}", inputs.join(" "), outputs.join(" ")))
+}
+
+/// A small, deterministic xorshift generator, good enough for shuffling fixtures.
+struct XorShift(u64);
+impl XorShift {
+    fn new(seed: u64) -> XorShift {
+        // xorshift gets stuck at zero, so mix the seed into a nonzero state.
+        XorShift(seed.wrapping_mul(0x9E37_79B9_7F4A_7C15) | 1)
+    }
+
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            let j = (self.next() % (i as u64 + 1)) as usize;
+            items.swap(i, j);
+        }
+    }
+}
+
+/// Every possible wiring of `n` wires, in lexicographic order.
+fn wirings(n: usize) -> Vec<Wiring> {
+    let mut all = Vec::new();
+    let mut cur: Vec<usize> = (0..n).collect();
+    loop {
+        all.push(Wiring(cur.clone()));
+        // Step to the next permutation: find the last ascent, swap its head with
+        // the smallest larger element after it, and reverse the tail.
+        let i = match (1..n).rev().find(|&i| cur[i - 1] < cur[i]) {
+            Some(i) => i - 1,
+            None => return all,
+        };
+        let j = (i + 1..n).rev().find(|&j| cur[j] > cur[i]).unwrap();
+        cur.swap(i, j);
+        cur[i + 1..].reverse();
+    }
+}
+
+fn letter(idx: usize) -> char {
+    (b'a' + idx as u8) as char
+}
+
+/// Maps each scrambled wire to the segment it is actually connected to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Wiring(Vec<usize>);
+impl Wiring {
+    fn apply(&self, wires: u32) -> u32 {
+        (0..self.0.len())
+            .filter(|&w| wires & 1 << w != 0)
+            .fold(0, |acc, w| acc | 1 << self.0[w])
+    }
+}
+impl std::fmt::Display for Wiring {
+    /// Formats as `a->c, b->f, ...`.
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let pairs: Vec<String> = self
+            .0
+            .iter()
+            .enumerate()
+            .map(|(w, &seg)| format!("{}->{}", letter(w), letter(seg)))
+            .collect();
+        write!(f, "{}", pairs.join(", "))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Decoding {
+    Unique(Wiring),
+    Inconsistent,
+    /// How many wirings fit; a lower bound unless `exact`, when the search
+    /// ran out of budget before finding them all.
+    Ambiguous {
+        count: usize,
+        exact: bool,
+    },
+}
+impl std::fmt::Display for Decoding {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Decoding::Unique(wiring) => write!(f, "{}", wiring),
+            Decoding::Inconsistent => write!(f, "no consistent wiring"),
+            Decoding::Ambiguous { count, exact: true } => {
+                write!(f, "ambiguous: {} wirings", count)
+            }
+            Decoding::Ambiguous {
+                count,
+                exact: false,
+            } => {
+                write!(f, "ambiguous: at least {} wirings", count)
+            }
+        }
+    }
 }
-fn overlap(s1: &[u8], s2: &[u8]) -> usize {
-    s1.iter().filter(|c| s2.contains(c)).count()
+
+/// Finds every wiring under which all of the sample's signals are glyphs.
+///
+/// Each signal's length narrows down which glyphs it could be, which in turn
+/// narrows down which segments its wires (and the wires it leaves dark) can
+/// drive. Once that has settled we backtrack over the remaining choices,
+/// pruning any partial wiring that leaves some signal without a matching glyph.
+/// Underdetermined samples on the larger alphabets have millions of wirings,
+/// so the search gives up after `SEARCH_BUDGET` partial wirings; that is only
+/// an error if it hasn't found two wirings by then.
+fn decode(sample: &Sample, alphabet: &Alphabet) -> anyhow::Result<Decoding> {
+    let mut signals: Vec<u32> = sample
+        .signals()
+        .map(|s| alphabet.mask(s))
+        .collect::<anyhow::Result<_>>()?;
+    signals.sort_unstable();
+    signals.dedup();
+
+    let n = alphabet.segments;
+    let all = (1u32 << n) - 1;
+    let mut domains = vec![all; n];
+    for &signal in &signals {
+        let candidates = alphabet
+            .glyphs
+            .iter()
+            .map(|&(_, g)| g)
+            .filter(|g| g.count_ones() == signal.count_ones());
+        let union = candidates.clone().fold(0, |acc, g| acc | g);
+        let inter = candidates.fold(all, |acc, g| acc & g);
+        for (w, domain) in domains.iter_mut().enumerate() {
+            *domain &= if signal & 1 << w != 0 { union } else { !inter };
+        }
+    }
+    // A wire with only one possible segment rules that segment out for the others.
+    loop {
+        let mut changed = false;
+        for w in 0..n {
+            if domains[w].count_ones() == 1 {
+                for other in (0..n).filter(|&o| o != w) {
+                    if domains[other] & domains[w] != 0 {
+                        domains[other] &= !domains[w];
+                        changed = true;
+                    }
+                }
+            }
+        }
+        if !changed {
+            break;
+        }
+    }
+    if domains.contains(&0) {
+        return Ok(Decoding::Inconsistent);
+    }
+
+    let mut search = WiringSearch {
+        glyphs: alphabet.glyphs.iter().map(|&(_, g)| g).collect(),
+        signals,
+        domains,
+        first: None,
+        count: 0,
+        budget: SEARCH_BUDGET,
+    };
+    search.run(&mut vec![None; n], 0);
+    let exact = search.budget > 0;
+    Ok(match (search.count, search.first) {
+        (count, _) if count > 1 => Decoding::Ambiguous { count, exact },
+        _ if !exact => return Err(anyhow!("gave up after {} partial wirings", SEARCH_BUDGET)),
+        (_, Some(wiring)) => Decoding::Unique(wiring),
+        (_, None) => Decoding::Inconsistent,
+    })
+}
+
+const SEARCH_BUDGET: usize = 100_000;
+
+struct WiringSearch {
+    glyphs: Vec<u32>,
+    signals: Vec<u32>,
+    domains: Vec<u32>,
+    first: Option<Wiring>,
+    count: usize,
+    /// Partial wirings left to try.
+    budget: usize,
+}
+impl WiringSearch {
+    fn run(&mut self, partial: &mut [Option<usize>], used: u32) {
+        if self.budget == 0 {
+            return;
+        }
+        self.budget -= 1;
+        let next = (0..partial.len())
+            .filter(|&w| partial[w].is_none())
+            .min_by_key(|&w| (self.domains[w] & !used).count_ones());
+        let w = match next {
+            Some(w) => w,
+            None => {
+                self.count += 1;
+                if self.first.is_none() {
+                    self.first = Some(Wiring(partial.iter().map(|s| s.unwrap()).collect()));
+                }
+                return;
+            }
+        };
+        for seg in 0..partial.len() {
+            if (self.domains[w] & !used) & 1 << seg == 0 {
+                continue;
+            }
+            partial[w] = Some(seg);
+            if self.feasible(partial) {
+                self.run(partial, used | 1 << seg);
+            }
+            partial[w] = None;
+        }
+    }
+
+    /// Whether every signal could still become a glyph: some glyph of the same
+    /// length must light all the segments its assigned wires drive, and none
+    /// of the segments driven by assigned wires outside of it.
+    fn feasible(&self, partial: &[Option<usize>]) -> bool {
+        self.signals.iter().all(|&signal| {
+            let (mut lit, mut dark) = (0u32, 0u32);
+            for (w, seg) in partial.iter().enumerate() {
+                if let Some(seg) = seg {
+                    if signal & 1 << w != 0 {
+                        lit |= 1 << seg;
+                    } else {
+                        dark |= 1 << seg;
+                    }
+                }
+            }
+            self.glyphs
+                .iter()
+                .any(|&g| g.count_ones() == signal.count_ones() && g & lit == lit && g & dark == 0)
+        })
+    }
 }
 
 #[cfg(test)]
@@ -133,7 +822,209 @@ mod test {
     fn small() -> anyhow::Result<()> {
         let input = parse_input(SMALL)?;
         assert_eq!(solve1(&input), 26);
-        assert_eq!(solve2(&input), 61229);
+        assert_eq!(solve2(&input)?, 61229);
+        Ok(())
+    }
+
+    #[test]
+    fn decoding() -> anyhow::Result<()> {
+        let sample = parse_sample(
+            "acedgfb cdfbe gcdfa fbcad dab cefabd cdfgeb eafb cagedb ab | cdfeb fcadb cdfeb cdbaf",
+        )?;
+        let seven = Alphabet::seven();
+        assert_eq!(
+            decode(&sample, &seven)?,
+            Decoding::Unique(Wiring(vec![2, 5, 6, 0, 1, 3, 4]))
+        );
+        assert_eq!(translated_sum(&sample)?, 5353);
+
+        // Only the "1" and "7" patterns: the other four wires are interchangeable.
+        let sample = parse_sample("ab dab | ab dab")?;
+        assert_eq!(
+            decode(&sample, &seven)?,
+            Decoding::Ambiguous {
+                count: 2 * 24,
+                exact: true
+            }
+        );
+        assert_eq!(
+            translated_sum(&sample).unwrap_err().to_string(),
+            "ambiguous: 48 wirings"
+        );
+
+        // Far too many wirings to count on the larger displays.
+        for (sample, alphabet) in [
+            ("abcdefgh | abc", Alphabet::sixteen()),
+            ("abcdefgh | ab", Alphabet::fourteen()),
+        ] {
+            let decoding = decode(&parse_sample(sample)?, &alphabet)?;
+            assert!(matches!(decoding, Decoding::Ambiguous { count, exact: false } if count > 1));
+            assert!(decoding.to_string().starts_with("ambiguous: at least "));
+        }
+
+        // No digit lights exactly one segment.
+        let sample = parse_sample("a | ab")?;
+        assert_eq!(decode(&sample, &seven)?, Decoding::Inconsistent);
+        assert_eq!(
+            translated_sum(&sample).unwrap_err().to_string(),
+            "no consistent wiring"
+        );
+
+        // Both the "1" and "7" patterns are fine on their own, but can't share "b".
+        let sample = parse_sample("ab cde | ab")?;
+        assert_eq!(decode(&sample, &seven)?, Decoding::Inconsistent);
+
+        assert!(decode(&parse_sample("abh | ab")?, &seven).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn report() -> anyhow::Result<()> {
+        let sample = parse_sample(
+            "acedgfb cdfbe gcdfa fbcad dab cefabd cdfgeb eafb cagedb ab | cdfeb fcadb cdfeb cdbaf",
+        )?;
+        let decoded = decode_sample(&sample, &Alphabet::seven())?;
+        assert_eq!(
+            decoded.wiring.to_string(),
+            "a->c, b->f, c->g, d->a, e->b, f->d, g->e"
+        );
+        assert_eq!(
+            decoded.to_string(),
+            "a->c, b->f, c->g, d->a, e->b, f->d, g->e | \
+             acedgfb=8 cdfbe=5 gcdfa=2 fbcad=3 dab=7 cefabd=9 cdfgeb=6 eafb=4 cagedb=0 ab=1 | \
+             cdfeb=5 fcadb=3 cdfeb=5 cdbaf=3"
+        );
+
+        let input = vec![sample, parse_sample("a | ab")?];
+        let report = json_report(&input, &Alphabet::seven());
+        assert!(report.starts_with(
+            r#"[{"line":1,"wiring":{"a":"c","b":"f","c":"g","d":"a","e":"b","f":"d","g":"e"},"inputs":[{"raw":"acedgfb","glyph":"8"},"#
+        ));
+        assert!(report.ends_with(
+            r#"{"raw":"cdbaf","glyph":"3"}],"text":"5353"},{"line":2,"error":"no consistent wiring"}]"#
+        ));
+
+        assert_eq!(json_string("a\"b\\c\n\u{1}é"), r#""a\"b\\c\u000a\u0001é""#);
+        let quoted: Alphabet = "'=a é=ab".parse()?;
+        let report = json_report(&[parse_sample("a ab | ab a")?], &quoted);
+        assert!(report.contains(r#"{"raw":"a","glyph":"'"}"#));
+        assert!(report.ends_with(r#""text":"é'"}]"#));
+        Ok(())
+    }
+
+    #[test]
+    fn rendering() -> anyhow::Result<()> {
+        assert_eq!(
+            render(
+                &Alphabet::seven()
+                    .glyphs
+                    .iter()
+                    .map(|&(_, g)| g)
+                    .collect::<Vec<_>>()
+            ),
+            vec![
+                " _       _   _       _   _   _   _   _ ",
+                "| |   |  _|  _| |_| |_  |_    | |_| |_|",
+                "|_|   | |_   _|   |  _| |_|   | |_|  _|",
+            ]
+        );
+        let sample = parse_sample(
+            "acedgfb cdfbe gcdfa fbcad dab cefabd cdfgeb eafb cagedb ab | cdfeb fcadb cdfeb cdbaf",
+        )?;
+        assert_eq!(
+            render_sample(&sample)?,
+            [
+                "scrambled         corrected",
+                "     _       _     _   _   _   _ ",
+                "|_| |_| |_| |_|   |_   _| |_   _|",
+                "| |   | | |   |    _|  _|  _|  _|",
+                "",
+            ]
+            .join("\n")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn round_trip() -> anyhow::Result<()> {
+        let all = wirings(7);
+        assert_eq!(all.len(), 5040);
+        let seven = Alphabet::seven();
+        for (idx, wiring) in all.into_iter().enumerate() {
+            let value = idx * 7919 % 10_000;
+            let line = encode(&wiring, value, idx as u64)?;
+            let sample = parse_sample(&line)?;
+            assert_eq!(sample.inputs.len(), 10);
+            let decoded = decode_sample(&sample, &seven)?;
+            assert_eq!(decoded.wiring, wiring, "{}", line);
+            assert_eq!(decoded.value()?, value, "{}", line);
+        }
+        assert!(encode(&Wiring(vec![0, 1, 2]), 0, 0).is_err());
+        assert!(encode(&Wiring(vec![7, 0, 1, 2, 3, 4, 5]), 1, 0).is_err());
+        assert!(encode(&Wiring(vec![0, 0, 1, 2, 3, 4, 5]), 1, 0).is_err());
+        assert!(encode(&wirings(7)[0], 10_000, 0).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn noisy() -> anyhow::Result<()> {
+        let seven = Alphabet::seven();
+        let expected = Wiring(vec![2, 5, 6, 0, 1, 3, 4]);
+
+        let clean = parse_sample(
+            "acedgfb cdfbe gcdfa fbcad dab cefabd cdfgeb eafb cagedb ab | cdfeb fcadb cdfeb cdbaf",
+        )?;
+        let decoded = decode_noisy(&clean, &seven, 0.05)?;
+        assert_eq!(decoded.wiring, expected);
+        assert_eq!(decoded.text(), "5353");
+        assert!(decoded.wiring_confidence > 0.9);
+        assert!(decoded.outputs.iter().all(|&(_, p)| p > 0.85));
+
+        // Two patterns missing, the first output lost its bottom segment and the
+        // "4" pattern gained a stray one.
+        let noisy =
+            parse_sample("acedgfb cdfbe gcdfa fbcad dab cefabd eafbg ab | dfeb fcadb cdfeb cdbaf")?;
+        assert!(decode(&noisy, &seven).is_ok_and(|d| d == Decoding::Inconsistent));
+        let decoded = decode_noisy(&noisy, &seven, 0.05)?;
+        assert_eq!(decoded.wiring, expected);
+        assert_eq!(decoded.text(), "5353");
+        assert!(decoded.outputs[0].1 < decoded.outputs[1].1);
+        assert!(decoded.outputs[0].1 > 0.7);
+
+        assert!(decode_noisy(&clean, &Alphabet::fourteen(), 0.05).is_err());
+        assert!(decode_noisy(&clean, &seven, 0.0).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn alphabets() -> anyhow::Result<()> {
+        let parsed: Alphabet =
+            "0=abcefg 1=cf 2=acdeg 3=acdfg 4=bcdf 5=abdfg 6=abdefg 7=acf 8=abcdefg 9=abcdfg"
+                .parse()?;
+        assert_eq!(parsed, Alphabet::seven());
+        assert!("0=ab 1=ba".parse::<Alphabet>().is_err());
+        assert!("0=abc 1=bd 2".parse::<Alphabet>().is_err());
+
+        for alphabet in [Alphabet::fourteen(), Alphabet::sixteen()] {
+            let alphabet = Alphabet::new(alphabet.segments, alphabet.glyphs)?;
+            // Reverse the wires, and show every glyph followed by a message.
+            let n = alphabet.segments;
+            let scramble = |c: char| -> Signal {
+                let (_, g) = alphabet.glyphs.iter().find(|&&(x, _)| x == c).unwrap();
+                (0..n)
+                    .filter(|seg| g & 1 << seg != 0)
+                    .map(|seg| letter(n - 1 - seg) as u8)
+                    .collect()
+            };
+            let sample = Sample {
+                inputs: alphabet.glyphs.iter().map(|&(c, _)| scramble(c)).collect(),
+                outputs: "HELLO2021".chars().map(scramble).collect(),
+            };
+            let decoded = decode_sample(&sample, &alphabet)?;
+            assert_eq!(decoded.wiring, Wiring((0..n).rev().collect()));
+            assert_eq!(decoded.text(), "HELLO2021");
+            assert!(decoded.value().is_err());
+        }
         Ok(())
     }
 
@@ -142,7 +1033,7 @@ mod test {
         let raw = std::fs::read_to_string("data/day08.input")?;
         let input = parse_input(&raw)?;
         assert_eq!(solve1(&input), 390);
-        assert_eq!(solve2(&input), 1011785);
+        assert_eq!(solve2(&input)?, 1011785);
         Ok(())
     }
 }
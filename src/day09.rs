@@ -1,5 +1,3 @@
-use std::collections::HashSet;
-
 use anyhow::anyhow;
 
 struct Grid {
@@ -12,7 +10,7 @@ impl Grid {
         if i < 0 || j < 0 || i >= self.height || j >= self.width {
             9
         } else {
-            self.values[(i as usize * self.width as usize + j as usize)]
+            self.values[i as usize * self.width as usize + j as usize]
         }
     }
 }
@@ -35,55 +33,357 @@ fn parse_input(input: &str) -> anyhow::Result<Grid> {
     })
 }
 
-fn solve1(grid: &Grid) -> u32 {
-    let mut risk = 0;
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+enum Adjacency {
+    #[default]
+    Four,
+    Eight,
+}
+impl Adjacency {
+    fn deltas(&self) -> &'static [(i32, i32)] {
+        match self {
+            Adjacency::Four => &[(-1, 0), (1, 0), (0, -1), (0, 1)],
+            Adjacency::Eight => &[
+                (-1, -1),
+                (-1, 0),
+                (-1, 1),
+                (0, -1),
+                (0, 1),
+                (1, -1),
+                (1, 0),
+                (1, 1),
+            ],
+        }
+    }
+}
+
+/// What counts as a low point. With `plateaus`, a connected region of equal
+/// height that is lower than everything around it is one low area; without,
+/// only cells strictly lower than all their neighbors are.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+struct LowPoints {
+    adjacency: Adjacency,
+    plateaus: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct LowArea {
+    height: u32,
+    cells: Vec<(i32, i32)>,
+}
+impl LowArea {
+    /// The puzzle's risk level, counted once per area however many cells it has.
+    fn risk(&self) -> u32 {
+        self.height + 1
+    }
+}
+
+/// The connected region of cells as high as `start`, in breadth-first order
+/// from it, marking each as `seen`.
+fn plateau(
+    grid: &Grid,
+    adjacency: Adjacency,
+    start: (i32, i32),
+    seen: &mut [bool],
+) -> Vec<(i32, i32)> {
+    let height = grid.get(start.0, start.1);
+    seen[(start.0 * grid.width + start.1) as usize] = true;
+    let mut cells = vec![start];
+    let mut next = 0;
+    while next < cells.len() {
+        let (i, j) = cells[next];
+        next += 1;
+        for &(di, dj) in adjacency.deltas() {
+            let (ni, nj) = (i + di, j + dj);
+            let inside = (0..grid.height).contains(&ni) && (0..grid.width).contains(&nj);
+            if inside && grid.get(ni, nj) == height {
+                let n = (ni * grid.width + nj) as usize;
+                if !seen[n] {
+                    seen[n] = true;
+                    cells.push((ni, nj));
+                }
+            }
+        }
+    }
+    cells
+}
+
+fn low_areas(grid: &Grid, options: &LowPoints) -> Vec<LowArea> {
+    let mut areas = Vec::new();
+    let mut seen = vec![false; grid.values.len()];
     for i in 0..grid.height {
         for j in 0..grid.width {
-            let v = grid.get(i, j);
-            if grid.get(i - 1, j) > v
-                && grid.get(i + 1, j) > v
-                && grid.get(i, j - 1) > v
-                && grid.get(i, j + 1) > v
-            {
-                risk += v + 1;
+            if seen[(i * grid.width + j) as usize] {
+                continue;
+            }
+            let height = grid.get(i, j);
+            let cells = if options.plateaus {
+                plateau(grid, options.adjacency, (i, j), &mut seen)
+            } else {
+                seen[(i * grid.width + j) as usize] = true;
+                vec![(i, j)]
+            };
+            // Equal neighbors are already part of the area when looking for
+            // plateaus, except past the edge, which counts as 9.
+            let lowest = cells.iter().all(|&(ci, cj)| {
+                options.adjacency.deltas().iter().all(|&(di, dj)| {
+                    let (ni, nj) = (ci + di, cj + dj);
+                    let inside = (0..grid.height).contains(&ni) && (0..grid.width).contains(&nj);
+                    let v = grid.get(ni, nj);
+                    v > height || (v == height && options.plateaus && inside)
+                })
+            });
+            if lowest {
+                areas.push(LowArea { height, cells });
             }
         }
     }
-    risk
+    areas
 }
 
-fn solve2(grid: &Grid) -> usize {
-    let mut explorer = Explorer {
-        grid,
-        vis: Default::default(),
-    };
+fn solve1(grid: &Grid) -> u32 {
+    low_areas(grid, &LowPoints::default())
+        .iter()
+        .map(|area| area.risk())
+        .sum()
+}
+
+/// Inclusive row and column ranges covered by a basin.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Bounds {
+    top: i32,
+    left: i32,
+    bottom: i32,
+    right: i32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Basin {
+    size: usize,
+    /// The basin's lowest cell, the first in reading order on ties.
+    low: (i32, i32),
+    bounds: Bounds,
+}
+
+/// Every cell below 9 labeled with the index of its basin in `basins`.
+struct Basins {
+    labels: Vec<u32>,
+    basins: Vec<Basin>,
+}
+const UNLABELED: u32 = u32::MAX;
+impl Basins {
+    fn label(&self, grid: &Grid, i: i32, j: i32) -> Option<usize> {
+        match self.labels[(i * grid.width + j) as usize] {
+            UNLABELED => None,
+            label => Some(label as usize),
+        }
+    }
+}
+
+/// Flood fills the regions between walls of 9 with an explicit stack, so
+/// basins of any size fit.
+fn label_basins(grid: &Grid) -> Basins {
+    let mut labels = vec![UNLABELED; grid.values.len()];
     let mut basins = Vec::new();
+    let mut stack = Vec::new();
     for i in 0..grid.height {
         for j in 0..grid.width {
-            let size = explorer.explore(i, j);
-            if size > 0 {
-                basins.push(size);
+            let idx = (i * grid.width + j) as usize;
+            if grid.values[idx] >= 9 || labels[idx] != UNLABELED {
+                continue;
+            }
+            let label = basins.len() as u32;
+            let mut basin = Basin {
+                size: 0,
+                low: (i, j),
+                bounds: Bounds {
+                    top: i,
+                    left: j,
+                    bottom: i,
+                    right: j,
+                },
+            };
+            labels[idx] = label;
+            stack.push((i, j));
+            while let Some((i, j)) = stack.pop() {
+                basin.size += 1;
+                let (low_i, low_j) = basin.low;
+                if (grid.get(i, j), i, j) < (grid.get(low_i, low_j), low_i, low_j) {
+                    basin.low = (i, j);
+                }
+                let b = &mut basin.bounds;
+                (b.top, b.bottom) = (b.top.min(i), b.bottom.max(i));
+                (b.left, b.right) = (b.left.min(j), b.right.max(j));
+                for (ni, nj) in [(i - 1, j), (i + 1, j), (i, j - 1), (i, j + 1)] {
+                    if grid.get(ni, nj) < 9 {
+                        let n = (ni * grid.width + nj) as usize;
+                        if labels[n] == UNLABELED {
+                            labels[n] = label;
+                            stack.push((ni, nj));
+                        }
+                    }
+                }
+            }
+            basins.push(basin);
+        }
+    }
+    Basins { labels, basins }
+}
+
+fn solve2(grid: &Grid) -> usize {
+    let mut sizes: Vec<usize> = label_basins(grid).basins.iter().map(|b| b.size).collect();
+    sizes.sort_unstable();
+    sizes.into_iter().rev().take(3).product()
+}
+
+/// Water flowing downhill: every cell drains into its lowest neighbor that is
+/// strictly lower than itself, the first in `Adjacency::deltas` order on
+/// ties. On a flat, water runs toward the nearest cell that has a lower
+/// neighbor; a flat without one is a lake, with a single sink at its first
+/// cell in reading order that the rest of it drains into.
+struct Drainage {
+    flow: Vec<Option<usize>>,
+    /// Index into `sinks` of the sink each cell ends up in.
+    basins: Vec<u32>,
+    sinks: Vec<usize>,
+    /// How many cells drain through each cell, counting itself.
+    accumulation: Vec<u32>,
+}
+
+fn drainage(grid: &Grid, adjacency: Adjacency) -> Drainage {
+    let mut flow: Vec<Option<usize>> = (0..grid.values.len())
+        .map(|idx| {
+            let (i, j) = (idx as i32 / grid.width, idx as i32 % grid.width);
+            let mut best = None;
+            let mut lowest = grid.values[idx];
+            for &(di, dj) in adjacency.deltas() {
+                let (ni, nj) = (i + di, j + dj);
+                if (0..grid.height).contains(&ni)
+                    && (0..grid.width).contains(&nj)
+                    && grid.get(ni, nj) < lowest
+                {
+                    lowest = grid.get(ni, nj);
+                    best = Some((ni * grid.width + nj) as usize);
+                }
+            }
+            best
+        })
+        .collect();
+
+    // How far each cell is from where water leaves its flat, so that water
+    // crossing a flat still runs from higher to lower `(height, distance)`.
+    let mut distance = vec![0; grid.values.len()];
+    let mut seen = vec![false; grid.values.len()];
+    let mut reached = vec![false; grid.values.len()];
+    for start in 0..grid.values.len() {
+        if seen[start] {
+            continue;
+        }
+        let start = (start as i32 / grid.width, start as i32 % grid.width);
+        let flat: Vec<usize> = plateau(grid, adjacency, start, &mut seen)
+            .into_iter()
+            .map(|(i, j)| (i * grid.width + j) as usize)
+            .collect();
+        let mut queue: Vec<usize> = flat
+            .iter()
+            .copied()
+            .filter(|&idx| flow[idx].is_some())
+            .collect();
+        if queue.is_empty() {
+            queue.push(flat[0]);
+        }
+        for &idx in &queue {
+            reached[idx] = true;
+        }
+        let mut next = 0;
+        while next < queue.len() {
+            let idx = queue[next];
+            next += 1;
+            let (i, j) = (idx as i32 / grid.width, idx as i32 % grid.width);
+            for &(di, dj) in adjacency.deltas() {
+                let (ni, nj) = (i + di, j + dj);
+                if (0..grid.height).contains(&ni)
+                    && (0..grid.width).contains(&nj)
+                    && grid.get(ni, nj) == grid.values[idx]
+                {
+                    let n = (ni * grid.width + nj) as usize;
+                    if !reached[n] {
+                        reached[n] = true;
+                        flow[n] = Some(idx);
+                        distance[n] = distance[idx] + 1;
+                        queue.push(n);
+                    }
+                }
+            }
+        }
+    }
+
+    // Water only runs downhill, so going up in height every cell's target
+    // has been handled before the cell itself, and going down every cell
+    // is complete before it passes its total on.
+    let mut order: Vec<usize> = (0..grid.values.len()).collect();
+    order.sort_by_key(|&idx| (grid.values[idx], distance[idx]));
+    let mut basins = vec![0; grid.values.len()];
+    let mut sinks = Vec::new();
+    for &idx in &order {
+        basins[idx] = match flow[idx] {
+            Some(to) => basins[to],
+            None => {
+                sinks.push(idx);
+                sinks.len() as u32 - 1
             }
+        };
+    }
+    let mut accumulation = vec![1; grid.values.len()];
+    for &idx in order.iter().rev() {
+        if let Some(to) = flow[idx] {
+            accumulation[to] += accumulation[idx];
         }
     }
-    basins.sort_unstable();
-    basins.into_iter().rev().take(3).product()
+    Drainage {
+        flow,
+        basins,
+        sinks,
+        accumulation,
+    }
 }
-struct Explorer<'a> {
-    grid: &'a Grid,
-    vis: HashSet<(i32, i32)>,
+
+/// Where drainage basins and the basins walled off by 9s disagree.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Comparison {
+    /// Cells below 9 that drain into a sink outside their walled basin.
+    crossings: Vec<(i32, i32)>,
+    /// Walled basins holding more than one sink, with those sinks.
+    splits: Vec<(usize, Vec<(i32, i32)>)>,
 }
-impl<'a> Explorer<'a> {
-    fn explore(&mut self, i: i32, j: i32) -> usize {
-        if self.grid.get(i, j) >= 9 || self.vis.contains(&(i, j)) {
-            return 0;
+
+fn compare_basins(grid: &Grid, adjacency: Adjacency) -> Comparison {
+    let walled = label_basins(grid);
+    let drained = drainage(grid, adjacency);
+    let coords = |idx: usize| (idx as i32 / grid.width, idx as i32 % grid.width);
+    let crossings = (0..grid.values.len())
+        .filter(|&idx| {
+            let sink = drained.sinks[drained.basins[idx] as usize];
+            grid.values[idx] < 9 && walled.labels[idx] != walled.labels[sink]
+        })
+        .map(coords)
+        .collect();
+    let mut sinks_in = vec![Vec::new(); walled.basins.len()];
+    for &sink in &drained.sinks {
+        if walled.labels[sink] != UNLABELED {
+            sinks_in[walled.labels[sink] as usize].push(sink);
         }
-        self.vis.insert((i, j));
-        1 + self.explore(i - 1, j)
-            + self.explore(i + 1, j)
-            + self.explore(i, j - 1)
-            + self.explore(i, j + 1)
     }
+    let splits = sinks_in
+        .into_iter()
+        .enumerate()
+        .filter(|(_, sinks)| sinks.len() > 1)
+        .map(|(label, mut sinks)| {
+            sinks.sort_unstable();
+            (label, sinks.into_iter().map(coords).collect())
+        })
+        .collect();
+    Comparison { crossings, splits }
 }
 
 #[cfg(test)]
@@ -106,6 +406,149 @@ mod test {
         Ok(())
     }
 
+    #[test]
+    fn low_points() -> anyhow::Result<()> {
+        let input = parse_input(SMALL)?;
+        let areas = low_areas(&input, &LowPoints::default());
+        assert_eq!(areas.len(), 4);
+        assert_eq!(areas[0].cells, vec![(0, 1)]);
+        let eight = LowPoints {
+            adjacency: Adjacency::Eight,
+            plateaus: false,
+        };
+        assert_eq!(low_areas(&input, &eight).len(), 4);
+
+        let plateau = parse_input("9999\n9119\n9999")?;
+        assert!(low_areas(&plateau, &LowPoints::default()).is_empty());
+        let areas = low_areas(
+            &plateau,
+            &LowPoints {
+                plateaus: true,
+                ..Default::default()
+            },
+        );
+        assert_eq!(
+            areas,
+            vec![LowArea {
+                height: 1,
+                cells: vec![(1, 1), (1, 2)]
+            }]
+        );
+        assert_eq!(areas[0].risk(), 2);
+
+        // The two 1s only touch diagonally.
+        let diagonal = parse_input("21\n12")?;
+        let count = |adjacency, plateaus| {
+            low_areas(
+                &diagonal,
+                &LowPoints {
+                    adjacency,
+                    plateaus,
+                },
+            )
+            .len()
+        };
+        assert_eq!(count(Adjacency::Four, false), 2);
+        assert_eq!(count(Adjacency::Four, true), 2);
+        assert_eq!(count(Adjacency::Eight, false), 0);
+        assert_eq!(count(Adjacency::Eight, true), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn basins() -> anyhow::Result<()> {
+        let input = parse_input(SMALL)?;
+        let labeled = label_basins(&input);
+        assert_eq!(labeled.basins.len(), 4);
+        assert_eq!(
+            labeled.basins[1],
+            Basin {
+                size: 9,
+                low: (0, 9),
+                bounds: Bounds {
+                    top: 0,
+                    left: 5,
+                    bottom: 2,
+                    right: 9
+                }
+            }
+        );
+        assert_eq!(labeled.label(&input, 0, 0), Some(0));
+        assert_eq!(labeled.label(&input, 0, 2), None);
+        assert_eq!(labeled.label(&input, 4, 9), Some(3));
+
+        // A single basin far too big for a recursive fill.
+        let flat = Grid {
+            width: 1500,
+            height: 1500,
+            values: vec![0; 1500 * 1500],
+        };
+        let labeled = label_basins(&flat);
+        assert_eq!(labeled.basins.len(), 1);
+        assert_eq!(labeled.basins[0].size, 1500 * 1500);
+        assert_eq!(labeled.basins[0].low, (0, 0));
+        Ok(())
+    }
+
+    #[test]
+    fn drainage_basins() -> anyhow::Result<()> {
+        let input = parse_input(SMALL)?;
+        let drained = drainage(&input, Adjacency::Four);
+        assert_eq!(drained.flow[0], Some(1));
+        assert_eq!(drained.flow[1], None);
+        let low_points: Vec<usize> = low_areas(&input, &LowPoints::default())
+            .iter()
+            .map(|area| (area.cells[0].0 * input.width + area.cells[0].1) as usize)
+            .collect();
+        assert!(low_points.iter().all(|idx| drained.sinks.contains(idx)));
+        assert_eq!(drained.accumulation[1], 6);
+        let drained_total: u32 = drained.sinks.iter().map(|&s| drained.accumulation[s]).sum();
+        assert_eq!(drained_total, 50);
+        // With four neighbors, water can't get past a wall of 9s.
+        assert_eq!(
+            compare_basins(&input, Adjacency::Four),
+            Comparison {
+                crossings: vec![],
+                splits: vec![]
+            }
+        );
+
+        let ridge = parse_input("0500\n9999\n1991")?;
+        let drained = drainage(&ridge, Adjacency::Four);
+        assert_eq!(drained.accumulation[0], 4);
+        assert_eq!(drained.basins[1], drained.basins[0]);
+        let comparison = compare_basins(&ridge, Adjacency::Eight);
+        // Two separate pools, the second one two cells wide.
+        assert_eq!(comparison.splits, vec![(0, vec![(0, 0), (0, 2)])]);
+        assert_eq!(comparison.crossings, vec![]);
+        let drained = drainage(&ridge, Adjacency::Four);
+        assert_eq!(drained.flow[3], Some(2));
+        assert_eq!(drained.accumulation[2], 4);
+
+        // The rim drains across itself into the lake, which is one sink.
+        let lake = parse_input("55555\n50005\n55555")?;
+        let drained = drainage(&lake, Adjacency::Four);
+        assert_eq!(drained.sinks, vec![6]);
+        assert_eq!(drained.accumulation[6], 15);
+        assert_eq!(drained.flow[0], Some(5));
+        assert_eq!(drained.flow[1], Some(6));
+        assert_eq!(
+            compare_basins(&lake, Adjacency::Four),
+            Comparison {
+                crossings: vec![],
+                splits: vec![]
+            }
+        );
+
+        let diagonal = parse_input("19\n90")?;
+        assert_eq!(
+            compare_basins(&diagonal, Adjacency::Eight).crossings,
+            vec![(0, 0)]
+        );
+        assert_eq!(compare_basins(&diagonal, Adjacency::Four).crossings, vec![]);
+        Ok(())
+    }
+
     #[test]
     fn normal() -> anyhow::Result<()> {
         let raw = std::fs::read_to_string("data/day09.input")?;
@@ -1,3 +1,7 @@
+use std::str::FromStr;
+
+use anyhow::anyhow;
+
 fn parse_input(input: &str) -> anyhow::Result<Vec<Vec<u8>>> {
     Ok(input
         .trim()
@@ -7,70 +11,690 @@ fn parse_input(input: &str) -> anyhow::Result<Vec<Vec<u8>>> {
 }
 
 fn solve1(inputs: &[Vec<u8>]) -> u64 {
+    let syntax = Syntax::default();
     inputs
         .iter()
-        .map(|input| match classify(input) {
-            Outcome::Valid { .. } => 0,
-            Outcome::Invalid { err } => score_error(err),
+        .map(|input| match syntax.classify(input) {
+            Outcome::Valid { .. } | Outcome::Unexpected { .. } => 0,
+            Outcome::Invalid { err, .. } => syntax.pairs[err].error_score,
         })
         .sum()
 }
 
+/// An opening and closing delimiter, which may be several bytes long, and how
+/// much it is worth when it shows up corrupted or has to be completed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Pair {
+    open: Vec<u8>,
+    close: Vec<u8>,
+    error_score: u64,
+    completion_score: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Syntax {
+    pairs: Vec<Pair>,
+    /// Completions are scored as digits in this base.
+    completion_base: u64,
+}
+impl Default for Syntax {
+    fn default() -> Self {
+        let pair = |open: u8, close: u8, error_score, completion_score| Pair {
+            open: vec![open],
+            close: vec![close],
+            error_score,
+            completion_score,
+        };
+        Syntax {
+            pairs: vec![
+                pair(b'(', b')', 3, 1),
+                pair(b'[', b']', 57, 2),
+                pair(b'{', b'}', 1197, 3),
+                pair(b'<', b'>', 25137, 4),
+            ],
+            completion_base: 5,
+        }
+    }
+}
+impl FromStr for Syntax {
+    type Err = anyhow::Error;
+
+    /// Parses one pair per line as `<open> <close> <error score> <completion score>`,
+    /// e.g. `begin end 10 5`.
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        let pairs = s
+            .trim()
+            .lines()
+            .map(
+                |line| match line.split_whitespace().collect::<Vec<_>>()[..] {
+                    [open, close, error_score, completion_score] => Ok(Pair {
+                        open: open.as_bytes().to_vec(),
+                        close: close.as_bytes().to_vec(),
+                        error_score: error_score.parse()?,
+                        completion_score: completion_score.parse()?,
+                    }),
+                    _ => Err(anyhow!("invalid delimiter pair: {}", line)),
+                },
+            )
+            .collect::<anyhow::Result<_>>()?;
+        Ok(Syntax {
+            pairs,
+            ..Syntax::default()
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Token {
+    Open(usize),
+    Close(usize),
+}
+
+/// A delimiter that was opened at `column` (1-based) and not yet closed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Open {
+    pair: usize,
+    column: usize,
+}
+
+#[derive(Debug, PartialEq, Eq)]
 enum Outcome {
-    Valid { stack: Vec<u8> },
-    Invalid { err: u8 },
-}
-fn classify(input: &[u8]) -> Outcome {
-    let mut stack = Vec::new();
-    for &b in input {
-        match b {
-            b'(' => stack.push(b')'),
-            b'[' => stack.push(b']'),
-            b'{' => stack.push(b'}'),
-            b'<' => stack.push(b'>'),
-            _ => {
-                if stack.pop() != Some(b) {
-                    return Outcome::Invalid { err: b };
+    /// Balanced so far; `stack` holds the pairs still open, innermost last.
+    Valid {
+        stack: Vec<Open>,
+    },
+    /// The closer of pair `err` showed up at `column` while `expected` was the
+    /// innermost open pair, or while nothing was open at all.
+    Invalid {
+        err: usize,
+        column: usize,
+        expected: Option<Open>,
+    },
+    Unexpected {
+        byte: u8,
+        column: usize,
+    },
+}
+
+/// A change to a line; columns are 1-based and refer to the original line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Edit {
+    Replace {
+        column: usize,
+        len: usize,
+        with: Vec<u8>,
+    },
+    /// Inserts `text` before `column`, which may be one past the end of the line.
+    Insert {
+        column: usize,
+        text: Vec<u8>,
+    },
+    Delete {
+        column: usize,
+        len: usize,
+    },
+}
+impl Edit {
+    fn column(&self) -> usize {
+        match self {
+            Edit::Replace { column, .. }
+            | Edit::Insert { column, .. }
+            | Edit::Delete { column, .. } => *column,
+        }
+    }
+}
+impl std::fmt::Display for Edit {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Edit::Replace { column, with, .. } => write!(
+                f,
+                "replace column {} with {}",
+                column,
+                String::from_utf8_lossy(with)
+            ),
+            Edit::Insert { column, text } => write!(
+                f,
+                "insert {} at column {}",
+                String::from_utf8_lossy(text),
+                column
+            ),
+            Edit::Delete { column, .. } => write!(f, "delete column {}", column),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Repair {
+    edits: Vec<Edit>,
+    line: Vec<u8>,
+}
+impl Repair {
+    fn new(input: &[u8], mut edits: Vec<Edit>) -> Repair {
+        // A stable sort keeps several insertions at one column in order.
+        edits.sort_by_key(|e| e.column());
+        let mut line = Vec::new();
+        let mut pos = 0;
+        for edit in &edits {
+            let at = edit.column() - 1;
+            line.extend_from_slice(&input[pos..at]);
+            pos = at;
+            match edit {
+                Edit::Replace { len, with, .. } => {
+                    line.extend_from_slice(with);
+                    pos += len;
                 }
+                Edit::Insert { text, .. } => line.extend_from_slice(text),
+                Edit::Delete { len, .. } => pos += len,
             }
         }
+        line.extend_from_slice(&input[pos..]);
+        Repair { edits, line }
     }
-    Outcome::Valid { stack }
 }
-fn score_error(b: u8) -> u64 {
-    match b {
-        b')' => 3,
-        b']' => 57,
-        b'}' => 1197,
-        b'>' => 25137,
-        _ => 0,
+
+impl Syntax {
+    /// Reads the longest delimiter starting at `input`. When a delimiter both
+    /// opens and closes, it closes the innermost open pair if it can, and
+    /// opens a new one otherwise.
+    fn token(&self, input: &[u8], innermost: Option<usize>) -> Option<(Token, usize)> {
+        let opens = self
+            .pairs
+            .iter()
+            .enumerate()
+            .map(|(idx, p)| (Token::Open(idx), &p.open));
+        let closes = self
+            .pairs
+            .iter()
+            .enumerate()
+            .map(|(idx, p)| (Token::Close(idx), &p.close));
+        opens
+            .chain(closes)
+            .filter(|(_, delim)| !delim.is_empty() && input.starts_with(delim))
+            .max_by_key(|&(token, delim)| {
+                let preference = match token {
+                    Token::Close(idx) if Some(idx) == innermost => 2,
+                    Token::Open(_) => 1,
+                    Token::Close(_) => 0,
+                };
+                (delim.len(), preference)
+            })
+            .map(|(token, delim)| (token, delim.len()))
     }
-}
 
-fn solve2(inputs: &[Vec<u8>]) -> u64 {
-    let mut scores: Vec<u64> = inputs
-        .iter()
-        .filter_map(|input| match classify(input) {
-            Outcome::Valid { stack } => Some(score_incomplete(&stack)),
-            Outcome::Invalid { .. } => None,
+    fn classify(&self, input: &[u8]) -> Outcome {
+        let mut stack: Vec<Open> = Vec::new();
+        let mut pos = 0;
+        while pos < input.len() {
+            let column = pos + 1;
+            let (token, len) = match self.token(&input[pos..], stack.last().map(|o| o.pair)) {
+                Some(t) => t,
+                None => {
+                    return Outcome::Unexpected {
+                        byte: input[pos],
+                        column,
+                    }
+                }
+            };
+            match token {
+                Token::Open(pair) => stack.push(Open { pair, column }),
+                Token::Close(idx) => match stack.pop() {
+                    Some(open) if open.pair == idx => {}
+                    expected => {
+                        return Outcome::Invalid {
+                            err: idx,
+                            column,
+                            expected,
+                        }
+                    }
+                },
+            }
+            pos += len;
+        }
+        Outcome::Valid { stack }
+    }
+
+    /// Splits a line into delimiters, with their columns and lengths, or
+    /// returns `None` if it contains anything else.
+    fn tokenize(&self, input: &[u8]) -> Option<Vec<(Token, usize, usize)>> {
+        let mut tokens = Vec::new();
+        let mut stack = Vec::new();
+        let mut pos = 0;
+        while pos < input.len() {
+            let (token, len) = self.token(&input[pos..], stack.last().copied())?;
+            match token {
+                Token::Open(idx) => stack.push(idx),
+                Token::Close(idx) => {
+                    if stack.last() == Some(&idx) {
+                        stack.pop();
+                    }
+                }
+            }
+            tokens.push((token, pos + 1, len));
+            pos += len;
+        }
+        Some(tokens)
+    }
+
+    /// Suggests fixes for a corrupted line: swapping the offending closer for
+    /// the expected one, if that leaves the line uncorrupted, and the fewest
+    /// insertions and deletions that balance the whole line. Other lines get no suggestions. Balancing is cubic in
+    /// the number of tokens, so lines with more than `MAX_BALANCED_TOKENS`
+    /// only get the swap.
+    fn suggest_repairs(&self, input: &[u8]) -> Vec<Repair> {
+        let (err, column, expected) = match self.classify(input) {
+            Outcome::Invalid {
+                err,
+                column,
+                expected,
+            } => (err, column, expected),
+            _ => return Vec::new(),
+        };
+        let mut repairs = Vec::new();
+        if let Some(open) = expected {
+            let replace = Edit::Replace {
+                column,
+                len: self.pairs[err].close.len(),
+                with: self.pairs[open.pair].close.clone(),
+            };
+            let repair = Repair::new(input, vec![replace]);
+            if !matches!(self.classify(&repair.line), Outcome::Invalid { .. }) {
+                repairs.push(repair);
+            }
+        }
+        if let Some(tokens) = self
+            .tokenize(input)
+            .filter(|tokens| tokens.len() <= MAX_BALANCED_TOKENS)
+        {
+            let mut balancer = Balancer::new(self, &tokens);
+            let mut edits = Vec::new();
+            balancer.fix(0, tokens.len(), input.len() + 1, &mut edits);
+            repairs.push(Repair::new(input, edits));
+        }
+        repairs
+    }
+
+    /// The closers that finish off `stack`, innermost first.
+    fn completion(&self, stack: &[Open]) -> Vec<u8> {
+        stack
+            .iter()
+            .rev()
+            .flat_map(|open| self.pairs[open.pair].close.iter().copied())
+            .collect()
+    }
+
+    /// Echoes every line, appending the completion to incomplete lines and
+    /// marking where corrupted lines go wrong.
+    fn annotate(&self, inputs: &[Vec<u8>]) -> String {
+        let mut out = String::new();
+        for input in inputs {
+            out += &String::from_utf8_lossy(input);
+            match self.classify(input) {
+                Outcome::Valid { stack } => {
+                    out += &String::from_utf8_lossy(&self.completion(&stack));
+                }
+                Outcome::Invalid {
+                    err,
+                    column,
+                    expected,
+                } => {
+                    let found = String::from_utf8_lossy(&self.pairs[err].close);
+                    out += &match expected {
+                        Some(open) => format!(
+                            "  <-- column {}: expected {}, found {}",
+                            column,
+                            String::from_utf8_lossy(&self.pairs[open.pair].close),
+                            found
+                        ),
+                        None => format!("  <-- column {}: unmatched {}", column, found),
+                    };
+                }
+                Outcome::Unexpected { byte, column } => {
+                    out += &format!(
+                        "  <-- column {}: unexpected {}",
+                        column,
+                        (byte as char).escape_default()
+                    );
+                }
+            }
+            out.push('\n');
+        }
+        out
+    }
+
+    fn completion_scores(&self, inputs: &[Vec<u8>]) -> Vec<u64> {
+        inputs
+            .iter()
+            .filter_map(|input| match self.classify(input) {
+                Outcome::Valid { stack } if !stack.is_empty() => {
+                    Some(self.score_incomplete(&stack))
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn score_incomplete(&self, stack: &[Open]) -> u64 {
+        stack.iter().rev().fold(0, |acc, open| {
+            self.completion_base * acc + self.pairs[open.pair].completion_score
         })
-        .collect();
-    scores.sort_unstable();
-    scores[scores.len() / 2]
+    }
 }
-fn score_incomplete(stack: &[u8]) -> u64 {
-    stack
-        .iter()
-        .rev()
-        .fold(0, |acc, &b| 5 * acc + score_incomplete_char(b))
+
+/// The longest line, in tokens, that `Syntax::suggest_repairs` balances:
+/// about 2 MB of table and 2e7 steps.
+const MAX_BALANCED_TOKENS: usize = 500;
+
+/// Finds the fewest edits that balance a line. Every token that doesn't end up
+/// in a matched pair costs one edit, either deleting it (closers) or inserting
+/// its partner (openers), so this maximizes the number of properly nested
+/// pairs with an interval DP over the tokens.
+struct Balancer<'a> {
+    syntax: &'a Syntax,
+    tokens: &'a [(Token, usize, usize)],
+    /// `pairs[i][j]`: the most pairs that can be matched within `tokens[i..j]`.
+    pairs: Vec<Vec<usize>>,
 }
-fn score_incomplete_char(b: u8) -> u64 {
-    match b {
-        b')' => 1,
-        b']' => 2,
-        b'}' => 3,
-        b'>' => 4,
-        _ => unreachable!("stack should not contain {}", b),
+impl<'a> Balancer<'a> {
+    fn new(syntax: &'a Syntax, tokens: &'a [(Token, usize, usize)]) -> Balancer<'a> {
+        let n = tokens.len();
+        let mut pairs = vec![vec![0; n + 1]; n + 1];
+        for i in (0..n).rev() {
+            for j in i + 1..=n {
+                let mut best = pairs[i + 1][j];
+                for k in i + 1..j {
+                    if Balancer::matches(tokens[i].0, tokens[k].0) {
+                        best = best.max(1 + pairs[i + 1][k] + pairs[k + 1][j]);
+                    }
+                }
+                pairs[i][j] = best;
+            }
+        }
+        Balancer {
+            syntax,
+            tokens,
+            pairs,
+        }
+    }
+
+    fn matches(open: Token, close: Token) -> bool {
+        matches!((open, close), (Token::Open(a), Token::Close(b)) if a == b)
+    }
+
+    /// Collects edits for `tokens[i..j]`, closing anything left open right
+    /// before column `end`.
+    fn fix(&mut self, i: usize, j: usize, end: usize, edits: &mut Vec<Edit>) {
+        if i == j {
+            return;
+        }
+        for k in i + 1..j {
+            if Balancer::matches(self.tokens[i].0, self.tokens[k].0)
+                && self.pairs[i][j] == 1 + self.pairs[i + 1][k] + self.pairs[k + 1][j]
+            {
+                self.fix(i + 1, k, self.tokens[k].1, edits);
+                self.fix(k + 1, j, end, edits);
+                return;
+            }
+        }
+        match self.tokens[i] {
+            (Token::Close(_), column, len) => {
+                edits.push(Edit::Delete { column, len });
+                self.fix(i + 1, j, end, edits);
+            }
+            (Token::Open(idx), _, _) => {
+                self.fix(i + 1, j, end, edits);
+                edits.push(Edit::Insert {
+                    column: end,
+                    text: self.syntax.pairs[idx].close.clone(),
+                });
+            }
+        }
+    }
+}
+
+/// A 1-based position within a file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+struct Location {
+    line: usize,
+    column: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Problem {
+    /// The closer of pair `found` doesn't match the innermost open pair, which
+    /// was opened at the given location, if there is one.
+    Corrupted {
+        at: Location,
+        found: usize,
+        expected: Option<(usize, Location)>,
+    },
+    Unclosed {
+        at: Location,
+        pair: usize,
+    },
+    /// A string or block comment that runs off the end of the file.
+    Unterminated {
+        at: Location,
+    },
+}
+impl Problem {
+    fn at(&self) -> Location {
+        match self {
+            Problem::Corrupted { at, .. }
+            | Problem::Unclosed { at, .. }
+            | Problem::Unterminated { at } => *at,
+        }
+    }
+}
+
+/// Delimiters plus the syntax for strings and comments, whose contents are
+/// skipped when checking whole files. Anything else that isn't a delimiter is
+/// ignored, and delimiters made of word characters (like `begin`) only count
+/// as whole words.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct FileSyntax {
+    syntax: Syntax,
+    strings: Vec<(Vec<u8>, Vec<u8>)>,
+    /// Inside a string, skips the byte after it.
+    escape: Option<u8>,
+    line_comments: Vec<Vec<u8>>,
+    block_comments: Vec<(Vec<u8>, Vec<u8>)>,
+}
+impl FileSyntax {
+    /// `"` and `'` strings with `\` escapes, and `//` and `/* */` comments.
+    fn c_like(syntax: Syntax) -> FileSyntax {
+        FileSyntax {
+            syntax,
+            strings: vec![
+                (b"\"".to_vec(), b"\"".to_vec()),
+                (b"'".to_vec(), b"'".to_vec()),
+            ],
+            escape: Some(b'\\'),
+            line_comments: vec![b"//".to_vec()],
+            block_comments: vec![(b"/*".to_vec(), b"*/".to_vec())],
+        }
+    }
+
+    /// Reports every corrupted, unclosed or unterminated construct in `source`,
+    /// in order. After a corrupted closer, checking resumes as if it closed the
+    /// nearest matching opener (leaving the openers in between unclosed), or
+    /// as if it weren't there if nothing matches.
+    fn check(&self, source: &[u8]) -> Vec<Problem> {
+        let line_starts: Vec<usize> = std::iter::once(0)
+            .chain(
+                (0..source.len())
+                    .filter(|&i| source[i] == b'\n')
+                    .map(|i| i + 1),
+            )
+            .collect();
+        let locate = |pos: usize| {
+            let line = line_starts.partition_point(|&start| start <= pos);
+            Location {
+                line,
+                column: pos - line_starts[line - 1] + 1,
+            }
+        };
+        let is_word = |b: u8| b.is_ascii_alphanumeric() || b == b'_';
+
+        let mut problems = Vec::new();
+        let mut stack: Vec<(usize, usize)> = Vec::new();
+        let mut pos = 0;
+        while pos < source.len() {
+            let rest = &source[pos..];
+            if self.line_comments.iter().any(|c| rest.starts_with(c)) {
+                pos += rest.iter().position(|&b| b == b'\n').unwrap_or(rest.len());
+                continue;
+            }
+            let skipped = self
+                .block_comments
+                .iter()
+                .map(|(open, close)| (open, close, None))
+                .chain(
+                    self.strings
+                        .iter()
+                        .map(|(open, close)| (open, close, self.escape)),
+                )
+                .find(|(open, _, _)| rest.starts_with(open));
+            if let Some((open, close, escape)) = skipped {
+                match FileSyntax::skip(&rest[open.len()..], close, escape) {
+                    Some(len) => pos += open.len() + len,
+                    None => {
+                        problems.push(Problem::Unterminated { at: locate(pos) });
+                        break;
+                    }
+                }
+                continue;
+            }
+            let token = self
+                .syntax
+                .token(rest, stack.last().map(|&(pair, _)| pair))
+                .filter(|&(_, len)| {
+                    let delim = &rest[..len];
+                    let starts_word = is_word(delim[0]) && pos > 0 && is_word(source[pos - 1]);
+                    let ends_word =
+                        is_word(delim[len - 1]) && rest.get(len).is_some_and(|&b| is_word(b));
+                    !starts_word && !ends_word
+                });
+            let (token, len) = match token {
+                Some(t) => t,
+                None => {
+                    pos += 1;
+                    continue;
+                }
+            };
+            match token {
+                Token::Open(pair) => stack.push((pair, pos)),
+                Token::Close(idx) => match stack.last() {
+                    Some(&(pair, _)) if pair == idx => {
+                        stack.pop();
+                    }
+                    innermost => {
+                        problems.push(Problem::Corrupted {
+                            at: locate(pos),
+                            found: idx,
+                            expected: innermost.map(|&(pair, at)| (pair, locate(at))),
+                        });
+                        // Openers skipped over to reach a match stay unclosed; the
+                        // innermost one is already named above.
+                        if let Some(depth) = stack.iter().rposition(|&(pair, _)| pair == idx) {
+                            let skipped = stack.len().saturating_sub(1);
+                            for &(pair, at) in &stack[depth + 1..skipped] {
+                                problems.push(Problem::Unclosed {
+                                    at: locate(at),
+                                    pair,
+                                });
+                            }
+                            stack.truncate(depth);
+                        }
+                    }
+                },
+            }
+            pos += len;
+        }
+        problems.extend(stack.into_iter().map(|(pair, at)| Problem::Unclosed {
+            at: locate(at),
+            pair,
+        }));
+        problems.sort_by_key(|p| p.at());
+        problems
+    }
+
+    /// The length of `input` up to and including `close`, if it shows up.
+    fn skip(input: &[u8], close: &[u8], escape: Option<u8>) -> Option<usize> {
+        let mut pos = 0;
+        while pos < input.len() {
+            if Some(input[pos]) == escape {
+                pos += 2;
+            } else if input[pos..].starts_with(close) {
+                return Some(pos + close.len());
+            } else {
+                pos += 1;
+            }
+        }
+        None
+    }
+}
+
+/// Part 2 asks for the middle score, which is only well defined for an odd
+/// number of incomplete lines; for even counts this takes the upper median.
+fn solve2(inputs: &[Vec<u8>]) -> Option<u64> {
+    let syntax = Syntax::default();
+    Some(Summary::new(syntax.completion_scores(inputs))?.upper_median())
+}
+
+/// Order statistics over a non-empty set of scores.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Summary {
+    sorted: Vec<u64>,
+}
+impl Summary {
+    fn new(mut scores: Vec<u64>) -> Option<Summary> {
+        if scores.is_empty() {
+            return None;
+        }
+        scores.sort_unstable();
+        Some(Summary { sorted: scores })
+    }
+
+    fn len(&self) -> usize {
+        self.sorted.len()
+    }
+
+    fn min(&self) -> u64 {
+        self.sorted[0]
+    }
+
+    fn max(&self) -> u64 {
+        self.sorted[self.len() - 1]
+    }
+
+    fn mean(&self) -> f64 {
+        let total: u128 = self.sorted.iter().map(|&s| s as u128).sum();
+        total as f64 / self.len() as f64
+    }
+
+    /// The smaller of the two middle scores when there is an even number of them.
+    fn lower_median(&self) -> u64 {
+        self.sorted[(self.len() - 1) / 2]
+    }
+
+    /// The larger of the two middle scores when there is an even number of them.
+    fn upper_median(&self) -> u64 {
+        self.sorted[self.len() / 2]
+    }
+
+    /// The average of the two middle scores when there is an even number of them.
+    fn median(&self) -> f64 {
+        (self.lower_median() as f64 + self.upper_median() as f64) / 2.0
+    }
+
+    /// The nearest-rank percentile: the smallest score that at least `p`
+    /// percent of scores are less than or equal to.
+    fn percentile(&self, p: f64) -> anyhow::Result<u64> {
+        if !(0.0..=100.0).contains(&p) {
+            return Err(anyhow!("percentile must be within [0, 100], got {}", p));
+        }
+        let rank = (p / 100.0 * self.len() as f64).ceil() as usize;
+        Ok(self.sorted[rank.max(1) - 1])
     }
 }
 
@@ -95,7 +719,264 @@ mod test {
     fn small() -> anyhow::Result<()> {
         let input = parse_input(SMALL)?;
         assert_eq!(solve1(&input), 26397);
-        assert_eq!(solve2(&input), 288957);
+        assert_eq!(solve2(&input), Some(288957));
+        Ok(())
+    }
+
+    #[test]
+    fn statistics() -> anyhow::Result<()> {
+        let input = parse_input(SMALL)?;
+        let summary = Summary::new(Syntax::default().completion_scores(&input)).unwrap();
+        assert_eq!(summary.len(), 5);
+        assert_eq!(summary.min(), 294);
+        assert_eq!(summary.max(), 1480781);
+        assert_eq!(
+            summary.mean(),
+            (294 + 5566 + 288957 + 995444 + 1480781) as f64 / 5.0
+        );
+        assert_eq!(summary.lower_median(), 288957);
+        assert_eq!(summary.upper_median(), 288957);
+        assert_eq!(summary.percentile(0.0)?, 294);
+        assert_eq!(summary.percentile(40.0)?, 5566);
+        assert_eq!(summary.percentile(41.0)?, 288957);
+        assert_eq!(summary.percentile(100.0)?, 1480781);
+        assert!(summary.percentile(101.0).is_err());
+
+        let even = Summary::new(vec![4, 1, 3, 2]).unwrap();
+        assert_eq!(even.lower_median(), 2);
+        assert_eq!(even.upper_median(), 3);
+        assert_eq!(even.median(), 2.5);
+
+        // Nothing incomplete: complete and corrupted lines only.
+        let input = parse_input("()\n[<>]\n(]")?;
+        assert_eq!(
+            Summary::new(Syntax::default().completion_scores(&input)),
+            None
+        );
+        assert_eq!(solve2(&input), None);
+        Ok(())
+    }
+
+    #[test]
+    fn custom_syntax() -> anyhow::Result<()> {
+        let syntax: Syntax = r"
+            begin end 10 1
+            /* */ 20 2
+            ( ) 30 3
+            | | 40 4
+        "
+        .parse()?;
+        let classify = |s: &str| syntax.classify(s.as_bytes());
+        let pairs = |outcome: Outcome| match outcome {
+            Outcome::Valid { stack } => Some(stack.iter().map(|o| o.pair).collect::<Vec<_>>()),
+            _ => None,
+        };
+        assert_eq!(pairs(classify("begin(/*()*/)end")), Some(vec![]));
+        assert_eq!(pairs(classify("begin(/*|")), Some(vec![0, 2, 1, 3]));
+        assert_eq!(pairs(classify("||")), Some(vec![]));
+        assert!(matches!(classify("(|)"), Outcome::Invalid { err: 2, .. }));
+        assert!(matches!(
+            classify("begin*/"),
+            Outcome::Invalid { err: 1, .. }
+        ));
+        assert!(matches!(
+            classify("begin x end"),
+            Outcome::Unexpected { byte: b' ', .. }
+        ));
+        let stack = [0, 2, 1, 3].map(|pair| Open { pair, column: 0 });
+        assert_eq!(
+            syntax.score_incomplete(&stack),
+            4 * 125 + 2 * 25 + 3 * 5 + 1
+        );
+
+        // Bytes that are neither openers nor closers no longer count as corruption.
+        let default = Syntax::default();
+        assert!(matches!(
+            default.classify(b"(a)"),
+            Outcome::Unexpected { byte: b'a', .. }
+        ));
+        assert!(matches!(
+            default.classify(b"(]"),
+            Outcome::Invalid { err: 1, .. }
+        ));
+        assert!("( ) 3".parse::<Syntax>().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn positions() -> anyhow::Result<()> {
+        let syntax = Syntax::default();
+        assert_eq!(
+            syntax.classify(b"{([(<{}[<>[]}>{[]{[(<()>"),
+            Outcome::Invalid {
+                err: 2,
+                column: 13,
+                expected: Some(Open { pair: 1, column: 8 })
+            }
+        );
+        assert_eq!(
+            syntax.classify(b"()>"),
+            Outcome::Invalid {
+                err: 3,
+                column: 3,
+                expected: None
+            }
+        );
+        assert_eq!(
+            syntax.classify(b"(x"),
+            Outcome::Unexpected {
+                byte: b'x',
+                column: 2
+            }
+        );
+        assert_eq!(
+            syntax.classify(b"[<"),
+            Outcome::Valid {
+                stack: vec![Open { pair: 1, column: 1 }, Open { pair: 3, column: 2 }]
+            }
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn repairs() -> anyhow::Result<()> {
+        let syntax = Syntax::default();
+        // Swapping in ")" only moves the problem to the last column.
+        let repairs = syntax.suggest_repairs(b"[(])");
+        assert_eq!(repairs.len(), 1);
+        assert_eq!(repairs[0].edits.len(), 2);
+        assert_eq!(repairs[0].line, b"[()]");
+
+        let repairs = syntax.suggest_repairs(b"[(<>]");
+        assert_eq!(repairs[0].line, b"[(<>)");
+        assert_eq!(repairs[0].edits[0].to_string(), "replace column 5 with )");
+        assert_eq!(repairs[1].line, b"[(<>)]");
+        assert_eq!(repairs[1].edits[0].to_string(), "insert ) at column 5");
+
+        let repairs = syntax.suggest_repairs(b"}(((");
+        assert_eq!(repairs.len(), 1);
+        assert_eq!(repairs[0].line, b"((()))");
+
+        for line in parse_input(SMALL)? {
+            for repair in syntax.suggest_repairs(&line) {
+                let edits = repair.edits.len();
+                assert!(edits >= 1);
+                let outcome = syntax.classify(&repair.line);
+                assert!(!matches!(outcome, Outcome::Invalid { .. }));
+                if !matches!(repair.edits[0], Edit::Replace { .. }) {
+                    assert_eq!(outcome, Outcome::Valid { stack: vec![] });
+                    // Never more edits than deleting every closer and closing every opener.
+                    assert!(edits <= line.len());
+                }
+            }
+        }
+        assert!(syntax.suggest_repairs(b"[<>").is_empty());
+
+        // Too long to balance, but the swap is still cheap.
+        let long = [b"[".repeat(400), b"(".repeat(200), b"]".to_vec()].concat();
+        let repairs = syntax.suggest_repairs(&long);
+        assert_eq!(repairs.len(), 1);
+        assert_eq!(repairs[0].line.last(), Some(&b')'));
+
+        let custom: Syntax = "begin end 1 1\n( ) 1 1".parse()?;
+        let repairs = custom.suggest_repairs(b"begin(end");
+        assert_eq!(repairs[0].line, b"begin()");
+        assert_eq!(repairs[1].line, b"begin()end");
+        Ok(())
+    }
+
+    #[test]
+    fn completions() -> anyhow::Result<()> {
+        let syntax = Syntax::default();
+        let input = parse_input(SMALL)?;
+        let completions: Vec<String> = input
+            .iter()
+            .filter_map(|line| match syntax.classify(line) {
+                Outcome::Valid { stack } => {
+                    Some(String::from_utf8_lossy(&syntax.completion(&stack)).into_owned())
+                }
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            completions,
+            vec!["}}]])})]", ")}>]})", "}}>}>))))", "]]}}]}]}>", "])}>"]
+        );
+
+        let annotated = syntax.annotate(&input);
+        let lines: Vec<&str> = annotated.lines().collect();
+        assert_eq!(lines[0], "[({(<(())[]>[[{[]{<()<>>}}]])})]");
+        assert_eq!(
+            lines[2],
+            "{([(<{}[<>[]}>{[]{[(<()>  <-- column 13: expected ], found }"
+        );
+        assert_eq!(
+            syntax.annotate(&[b"()>".to_vec(), b"(x".to_vec()]),
+            "()>  <-- column 3: unmatched >\n(x  <-- column 2: unexpected x\n"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn files() -> anyhow::Result<()> {
+        let file = FileSyntax::c_like(Syntax::default());
+        let source = br#"fn main() {
+    let s = "not a bracket: ) ] }";
+    let c = '}'; // nor this: (
+    /* or [ this
+       one */
+    if x < 3 { call(a, [b) }
+    let v = vec![1, 2;
+}
+"#;
+        let loc = |line, column| Location { line, column };
+        assert_eq!(
+            file.check(source),
+            vec![
+                Problem::Unclosed {
+                    at: loc(6, 10),
+                    pair: 3
+                },
+                Problem::Corrupted {
+                    at: loc(6, 26),
+                    found: 0,
+                    expected: Some((1, loc(6, 24)))
+                },
+                Problem::Corrupted {
+                    at: loc(8, 1),
+                    found: 2,
+                    expected: Some((1, loc(7, 17)))
+                },
+            ]
+        );
+        assert_eq!(file.check(b"[()]\n{\n}\n"), vec![]);
+        assert_eq!(
+            file.check(b"(\n\"oops)\n"),
+            vec![
+                Problem::Unclosed {
+                    at: loc(1, 1),
+                    pair: 0
+                },
+                Problem::Unterminated { at: loc(2, 1) }
+            ]
+        );
+
+        let pascal = FileSyntax {
+            syntax: "begin end 1 1\n( ) 1 1".parse()?,
+            strings: vec![(b"'".to_vec(), b"'".to_vec())],
+            escape: None,
+            line_comments: vec![],
+            block_comments: vec![(b"{".to_vec(), b"}".to_vec())],
+        };
+        let source = b"begin\n  beginning := 'end'; { end }\n  f(x;\nend";
+        assert_eq!(
+            pascal.check(source),
+            vec![Problem::Corrupted {
+                at: loc(4, 1),
+                found: 0,
+                expected: Some((1, loc(3, 4)))
+            }]
+        );
         Ok(())
     }
 
@@ -104,7 +985,7 @@ mod test {
         let raw = std::fs::read_to_string("data/day10.input")?;
         let input = parse_input(&raw)?;
         assert_eq!(solve1(&input), 345441);
-        assert_eq!(solve2(&input), 3235371166);
+        assert_eq!(solve2(&input), Some(3235371166));
         Ok(())
     }
 }
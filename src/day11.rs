@@ -1,69 +1,497 @@
-use std::collections::HashSet;
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap, HashSet, VecDeque},
+    fmt,
+    hash::{Hash, Hasher},
+    io::Write,
+    path::{Path, PathBuf},
+    time::Duration,
+};
 
-#[derive(Clone)]
-struct Grid([u32; 100]);
+use anyhow::anyhow;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Neighbors {
+    /// Up, down, left and right.
+    Four,
+    /// Diagonals too.
+    Eight,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Topology {
+    neighbors: Neighbors,
+    /// Whether the edges wrap around, making the grid a torus.
+    wrap: bool,
+}
+impl Default for Topology {
+    fn default() -> Self {
+        Topology {
+            neighbors: Neighbors::Eight,
+            wrap: false,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Grid {
+    width: usize,
+    height: usize,
+    values: Vec<u32>,
+    topology: Topology,
+}
 fn parse_input(input: &str) -> anyhow::Result<Grid> {
-    let values: Vec<u32> = input.chars().filter_map(|c| c.to_digit(10)).collect();
-    Ok(Grid(values.try_into().unwrap()))
+    let mut values = Vec::new();
+    let mut width = 0;
+    let mut height = 0;
+    for line in input.trim().lines().map(|l| l.trim()) {
+        if height > 0 && line.len() != width {
+            return Err(anyhow!(
+                "expected {} cells, got {}: {}",
+                width,
+                line.len(),
+                line
+            ));
+        }
+        width = line.len();
+        height += 1;
+        for c in line.chars() {
+            values.push(
+                c.to_digit(10)
+                    .ok_or_else(|| anyhow!("invalid digit: {}", c))?,
+            );
+        }
+    }
+    if values.is_empty() {
+        return Err(anyhow!("empty grid"));
+    }
+    Ok(Grid {
+        width,
+        height,
+        values,
+        topology: Topology::default(),
+    })
 }
 impl Grid {
-    fn step(&mut self) {
+    fn with_topology(self, topology: Topology) -> Grid {
+        Grid { topology, ..self }
+    }
+
+    /// The index of the cell at offset `(di, dj)` from `(i, j)`, if there is one.
+    fn offset(&self, i: usize, j: usize, di: i32, dj: i32) -> Option<usize> {
+        let (h, w) = (self.height as i64, self.width as i64);
+        let (mut i, mut j) = (i as i64 + di as i64, j as i64 + dj as i64);
+        if self.topology.wrap {
+            i = i.rem_euclid(h);
+            j = j.rem_euclid(w);
+        } else if !(0..h).contains(&i) || !(0..w).contains(&j) {
+            return None;
+        }
+        Some((i * w + j) as usize)
+    }
+
+    /// The distinct cells around `cell`. On a torus less than three cells wide
+    /// or tall several offsets wrap onto the same cell, or back onto `cell`
+    /// itself; each neighbor is listed once, and `cell` never is.
+    fn neighbors(&self, cell: usize) -> impl Iterator<Item = usize> {
+        let (i, j) = (cell / self.width, cell % self.width);
+        let mut found = [0; 8];
+        let mut len = 0;
+        for &(di, dj) in self.deltas() {
+            if let Some(n) = self.offset(i, j, di, dj) {
+                if n != cell && !found[..len].contains(&n) {
+                    found[len] = n;
+                    len += 1;
+                }
+            }
+        }
+        found.into_iter().take(len)
+    }
+
+    fn deltas(&self) -> &'static [(i32, i32)] {
+        match self.topology.neighbors {
+            Neighbors::Four => &[(-1, 0), (1, 0), (0, -1), (0, 1)],
+            Neighbors::Eight => &[
+                (-1, -1),
+                (-1, 0),
+                (-1, 1),
+                (0, -1),
+                (0, 1),
+                (1, -1),
+                (1, 0),
+                (1, 1),
+            ],
+        }
+    }
+
+    /// Advances one step and returns how many octopuses flashed.
+    fn step(&mut self) -> usize {
         let mut flashing = Vec::new();
         let mut flashed = HashSet::new();
-        let mut tmp = self.0;
-        let mut bump = |i: i32, j: i32| -> bool {
-            if !(0..10).contains(&i) || !(0..10).contains(&j) {
-                return false;
-            }
-            let idx = (10 * i + j) as usize;
+        let mut tmp = self.values.clone();
+        let mut bump = |idx: usize| -> bool {
             tmp[idx] += 1;
-            tmp[idx] > 9 && flashed.insert((i, j))
+            tmp[idx] > 9 && flashed.insert(idx)
         };
-        for i in 0..10 {
-            for j in 0..10 {
-                if bump(i, j) {
-                    flashing.push((i, j));
+        for idx in 0..self.values.len() {
+            if bump(idx) {
+                flashing.push(idx);
+            }
+        }
+        while let Some(idx) = flashing.pop() {
+            for n in self.neighbors(idx) {
+                if bump(n) {
+                    flashing.push(n);
                 }
             }
         }
-        while let Some((i, j)) = flashing.pop() {
-            for di in -1..=1 {
-                for dj in -1..=1 {
-                    if di == 0 && dj == 0 {
-                        continue;
-                    }
-                    if bump(i + di, j + dj) {
-                        flashing.push((i + di, j + dj));
-                    }
+        for &idx in &flashed {
+            tmp[idx] = 0;
+        }
+        std::mem::swap(&mut self.values, &mut tmp);
+        flashed.len()
+    }
+
+    /// Like `step`, but records every flash in the order it happened. Flashes
+    /// are processed breadth first, so a flash's depth is one more than that
+    /// of the flash whose energy pushed it over the edge.
+    fn step_traced(&mut self) -> Vec<Flash> {
+        let mut flashes = Vec::new();
+        let mut queue = VecDeque::new();
+        let mut flashed = vec![false; self.values.len()];
+        for (cell, v) in self.values.iter_mut().enumerate() {
+            *v += 1;
+            if *v > 9 {
+                flashed[cell] = true;
+                queue.push_back(Flash { cell, depth: 1 });
+            }
+        }
+        while let Some(flash) = queue.pop_front() {
+            for n in self.neighbors(flash.cell) {
+                self.values[n] += 1;
+                if self.values[n] > 9 && !flashed[n] {
+                    flashed[n] = true;
+                    queue.push_back(Flash {
+                        cell: n,
+                        depth: flash.depth + 1,
+                    });
                 }
             }
+            flashes.push(flash);
+        }
+        for flash in &flashes {
+            self.values[flash.cell] = 0;
         }
-        for &(i, j) in &flashed {
-            tmp[(10 * i + j) as usize] = 0;
+        flashes
+    }
+
+    /// Every step from here on, with what flashed and the running totals.
+    fn cascades(&self) -> Cascades {
+        Cascades {
+            step: 0,
+            totals: vec![0; self.values.len()],
+            cur: self.clone(),
+        }
+    }
+
+    fn fingerprint(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.values.hash(&mut hasher);
+        hasher.finish()
+    }
+}
+
+/// Steps a grid in place, keeping its scratch space between steps so large
+/// grids don't pay for a fresh set and a copy of the board every time.
+struct Stepper {
+    grid: Grid,
+    /// One bit per cell, set while the cell has flashed this step.
+    flashed: Vec<u64>,
+    /// Cells that flashed this step, in order; those past `next` still have
+    /// to pass their energy on.
+    queue: Vec<usize>,
+    /// Index offsets of the neighbors of any cell away from the edges.
+    strides: Vec<isize>,
+}
+impl Stepper {
+    fn new(grid: Grid) -> Stepper {
+        let strides = grid
+            .deltas()
+            .iter()
+            .map(|&(di, dj)| di as isize * grid.width as isize + dj as isize)
+            .collect();
+        Stepper {
+            flashed: vec![0; grid.values.len().div_ceil(64)],
+            queue: Vec::new(),
+            strides,
+            grid,
+        }
+    }
+
+    fn bump(&mut self, cell: usize) {
+        self.grid.values[cell] += 1;
+        if self.grid.values[cell] > 9 {
+            self.flash(cell);
+        }
+    }
+
+    fn flash(&mut self, cell: usize) {
+        let (word, bit) = (cell / 64, 1 << (cell % 64));
+        if self.flashed[word] & bit == 0 {
+            self.flashed[word] |= bit;
+            self.queue.push(cell);
+        }
+    }
+
+    /// Same as `Grid::step`.
+    fn step(&mut self) -> usize {
+        self.queue.clear();
+        for cell in 0..self.grid.values.len() {
+            self.bump(cell);
+        }
+        let mut next = 0;
+        while next < self.queue.len() {
+            let cell = self.queue[next];
+            next += 1;
+            let (i, j) = (cell / self.grid.width, cell % self.grid.width);
+            if (1..self.grid.height - 1).contains(&i) && (1..self.grid.width - 1).contains(&j) {
+                for k in 0..self.strides.len() {
+                    self.bump(cell.wrapping_add_signed(self.strides[k]));
+                }
+            } else {
+                for n in self.grid.neighbors(cell) {
+                    self.bump(n);
+                }
+            }
+        }
+        for &cell in &self.queue {
+            self.grid.values[cell] = 0;
+            self.flashed[cell / 64] = 0;
+        }
+        self.queue.len()
+    }
+}
+
+/// The grid's states eventually repeat: the state after `start + period`
+/// steps is the same as after `start` steps. `flashes[k]` is the number of
+/// flashes during step `k + 1`, for every step up to the first repeat.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Cycle {
+    start: usize,
+    period: usize,
+    flashes: Vec<usize>,
+}
+impl Cycle {
+    fn flashes_after(&self, steps: u64) -> u128 {
+        let prefix = |n: usize| -> u128 { self.flashes[..n].iter().map(|&f| f as u128).sum() };
+        if steps <= self.flashes.len() as u64 {
+            return prefix(steps as usize);
+        }
+        let looped = steps - self.start as u64;
+        let (laps, rest) = (
+            looped / self.period as u64,
+            (looped % self.period as u64) as usize,
+        );
+        let lap = prefix(self.start + self.period) - prefix(self.start);
+        prefix(self.start) + laps as u128 * lap + prefix(self.start + rest) - prefix(self.start)
+    }
+
+    /// The first step after which every octopus has just flashed, if ever.
+    fn synchronized(&self, cells: usize) -> Option<usize> {
+        self.flashes.iter().position(|&f| f == cells).map(|k| k + 1)
+    }
+}
+
+/// Steps the grid until it revisits a state, giving up after `limit` steps.
+/// States are compared by hash, and a matching hash is confirmed by replaying
+/// the earlier state, so only a fingerprint of each state is kept around.
+fn find_cycle(grid: &Grid, limit: usize) -> Option<Cycle> {
+    let mut cur = grid.clone();
+    let mut seen: HashMap<u64, Vec<usize>> = HashMap::new();
+    let mut flashes = Vec::new();
+    seen.insert(cur.fingerprint(), vec![0]);
+    for step in 1..=limit {
+        flashes.push(cur.step());
+        let candidates = seen.entry(cur.fingerprint()).or_default();
+        for &start in candidates.iter() {
+            let mut earlier = grid.clone();
+            for _ in 0..start {
+                earlier.step();
+            }
+            if earlier.values == cur.values {
+                return Some(Cycle {
+                    start,
+                    period: step - start,
+                    flashes,
+                });
+            }
         }
-        std::mem::swap(&mut self.0, &mut tmp);
+        candidates.push(step);
     }
+    None
 }
 
 fn solve1(grid: &Grid) -> usize {
-    let mut grid = grid.clone();
-    let mut total = 0;
-    for _ in 0..100 {
-        grid.step();
-        total += grid.0.iter().filter(|&&v| v == 0).count();
+    let mut stepper = Stepper::new(grid.clone());
+    (0..100).map(|_| stepper.step()).sum()
+}
+
+/// The first step on which every octopus flashes, or `None` if the grid
+/// settles into a cycle without that ever happening.
+fn solve2(grid: &Grid) -> Option<usize> {
+    // Every cell stays in 0..=9, so there are finitely many states and the
+    // grid always ends up in a cycle.
+    find_cycle(grid, usize::MAX)?.synchronized(grid.values.len())
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Flash {
+    cell: usize,
+    depth: usize,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct StepReport {
+    step: usize,
+    /// In the order they went off.
+    flashes: Vec<Flash>,
+    /// The longest chain of flashes setting each other off, 0 if none flashed.
+    depth: usize,
+    /// How often each cell has flashed so far, including this step.
+    totals: Vec<u64>,
+}
+
+struct Cascades {
+    step: usize,
+    totals: Vec<u64>,
+    cur: Grid,
+}
+impl Iterator for Cascades {
+    type Item = StepReport;
+
+    fn next(&mut self) -> Option<StepReport> {
+        let flashes = self.cur.step_traced();
+        self.step += 1;
+        for flash in &flashes {
+            self.totals[flash.cell] += 1;
+        }
+        Some(StepReport {
+            step: self.step,
+            depth: flashes.iter().map(|f| f.depth).max().unwrap_or(0),
+            flashes,
+            totals: self.totals.clone(),
+        })
     }
-    total
 }
 
-fn solve2(grid: &Grid) -> usize {
-    let mut grid = grid.clone();
-    for step in 1.. {
-        grid.step();
-        if grid.0.iter().all(|&v| v == 0) {
-            return step;
+/// The grid after `step` steps, and which cells flashed on the last one.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Frame {
+    step: usize,
+    width: usize,
+    values: Vec<u32>,
+    flashed: Vec<bool>,
+}
+impl fmt::Display for Frame {
+    /// One digit per cell, with the cells that just flashed in bold yellow.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (row, flashed) in self
+            .values
+            .chunks(self.width)
+            .zip(self.flashed.chunks(self.width))
+        {
+            for (v, &lit) in row.iter().zip(flashed) {
+                if lit {
+                    write!(f, "\x1b[1;33m{}\x1b[0m", v)?;
+                } else {
+                    write!(f, "{}", v)?;
+                }
+            }
+            writeln!(f)?;
         }
+        Ok(())
     }
-    0
+}
+impl Frame {
+    /// A binary PGM image with each cell drawn as a `scale`x`scale` square.
+    /// Energy levels are shades of gray, and flashing cells are white.
+    fn to_pgm(&self, scale: usize) -> Vec<u8> {
+        let height = self.values.len() / self.width;
+        let mut out = format!("P5\n{} {}\n255\n", self.width * scale, height * scale).into_bytes();
+        for i in 0..height {
+            let row: Vec<u8> = (0..self.width * scale)
+                .map(|x| {
+                    let cell = i * self.width + x / scale;
+                    if self.flashed[cell] {
+                        255
+                    } else {
+                        self.values[cell] as u8 * 20
+                    }
+                })
+                .collect();
+            for _ in 0..scale {
+                out.extend_from_slice(&row);
+            }
+        }
+        out
+    }
+}
+
+/// The initial grid, then the grid after every step.
+fn frames(grid: &Grid) -> impl Iterator<Item = Frame> {
+    let mut cur = grid.clone();
+    (0..).map(move |step| {
+        let mut flashed = vec![false; cur.values.len()];
+        if step > 0 {
+            for flash in cur.step_traced() {
+                flashed[flash.cell] = true;
+            }
+        }
+        Frame {
+            step,
+            width: cur.width,
+            values: cur.values.clone(),
+            flashed,
+        }
+    })
+}
+
+/// Plays `steps` steps in the terminal, redrawing at `frame_rate` frames per second.
+fn replay(grid: &Grid, steps: usize, frame_rate: f64, out: &mut impl Write) -> anyhow::Result<()> {
+    let delay = Duration::try_from_secs_f64(1.0 / frame_rate)
+        .map_err(|e| anyhow!("invalid frame rate {}: {}", frame_rate, e))?;
+    for frame in frames(grid).take(steps.saturating_add(1)) {
+        let count = frame.flashed.iter().filter(|&&lit| lit).count();
+        write!(
+            out,
+            "\x1b[H\x1b[2Jstep {}: {} flashes\n{}",
+            frame.step, count, frame
+        )?;
+        out.flush()?;
+        std::thread::sleep(delay);
+    }
+    Ok(())
+}
+
+/// Writes the initial grid and `steps` further frames to `dir` as
+/// `frame0000.pgm`, `frame0001.pgm`, ..., returning the paths written.
+fn export_frames(
+    grid: &Grid,
+    steps: usize,
+    dir: &Path,
+    scale: usize,
+) -> anyhow::Result<Vec<PathBuf>> {
+    if scale == 0 {
+        return Err(anyhow!("scale must be positive"));
+    }
+    std::fs::create_dir_all(dir)?;
+    frames(grid)
+        .take(steps.saturating_add(1))
+        .map(|frame| {
+            let path = dir.join(format!("frame{:04}.pgm", frame.step));
+            std::fs::write(&path, frame.to_pgm(scale))?;
+            Ok(path)
+        })
+        .collect()
 }
 
 #[cfg(test)]
@@ -87,7 +515,240 @@ mod test {
     fn small() -> anyhow::Result<()> {
         let input = parse_input(SMALL)?;
         assert_eq!(solve1(&input), 1656);
-        assert_eq!(solve2(&input), 195);
+        assert_eq!(solve2(&input), Some(195));
+        Ok(())
+    }
+
+    #[test]
+    fn cycles() -> anyhow::Result<()> {
+        let input = parse_input(SMALL)?;
+        let cycle = find_cycle(&input, 1000).unwrap();
+        // Once synchronized, the whole grid flashes every ten steps.
+        assert_eq!(cycle.synchronized(100), Some(195));
+        assert_eq!(cycle.period, 10);
+        assert_eq!(cycle.flashes_after(10), 204);
+        assert_eq!(cycle.flashes_after(100), 1656);
+        let mut grid = input.clone();
+        let simulated: usize = (0..1234).map(|_| grid.step()).sum();
+        assert_eq!(cycle.flashes_after(1234), simulated as u128);
+        assert_eq!(
+            cycle.flashes_after(1_000_000_000_000),
+            cycle.flashes_after(1_000_000_000_000 - 10) + 100
+        );
+        assert_eq!(find_cycle(&input, 100), None);
+
+        // Four-neighbor flashes are too weak to ever synchronize this row.
+        let lonely = parse_input("900")?.with_topology(Topology {
+            neighbors: Neighbors::Four,
+            wrap: false,
+        });
+        assert_eq!(solve2(&lonely), None);
+        let cycle = find_cycle(&lonely, 1000).unwrap();
+        assert_eq!((cycle.start, cycle.period), (0, 9));
+        assert_eq!(cycle.synchronized(3), None);
+        assert_eq!(cycle.flashes_after(10), 4);
+        Ok(())
+    }
+
+    #[test]
+    fn cascades() -> anyhow::Result<()> {
+        let input = parse_input(SMALL)?;
+        let reports: Vec<StepReport> = input.cascades().take(100).collect();
+        assert_eq!((reports[0].step, reports[0].depth), (1, 0));
+        assert_eq!(reports[1].flashes.len(), 35);
+        assert_eq!(reports[99].totals.iter().sum::<u64>(), 1656);
+        let mut grid = input.clone();
+        for report in &reports {
+            grid.step();
+            let zeros: Vec<usize> = (0..100).filter(|&c| grid.values[c] == 0).collect();
+            let mut cells: Vec<usize> = report.flashes.iter().map(|f| f.cell).collect();
+            cells.sort_unstable();
+            assert_eq!(cells, zeros);
+        }
+
+        // The ring goes off first, and together they set off the middle.
+        let ring = parse_input("11111\n19991\n19191\n19991\n11111")?;
+        let report = ring.cascades().next().unwrap();
+        assert_eq!(report.depth, 2);
+        assert_eq!(report.flashes.len(), 9);
+        assert_eq!(report.flashes[8], Flash { cell: 12, depth: 2 });
+        assert!(report.flashes[..8].iter().all(|f| f.depth == 1));
+        Ok(())
+    }
+
+    #[test]
+    fn rendering() -> anyhow::Result<()> {
+        let grid = parse_input("11111\n19991\n19191\n19991\n11111")?;
+        let frame = frames(&grid).nth(1).unwrap();
+        let text = frame.to_string();
+        assert_eq!(text.lines().next(), Some("34543"));
+        assert_eq!(
+            text.lines().nth(2),
+            Some("5\x1b[1;33m0\x1b[0m\x1b[1;33m0\x1b[0m\x1b[1;33m0\x1b[0m5")
+        );
+
+        let mut out = Vec::new();
+        replay(&grid, 2, 1e6, &mut out)?;
+        let out = String::from_utf8(out)?;
+        assert_eq!(out.matches("\x1b[2J").count(), 3);
+        assert!(out.contains("step 1: 9 flashes\n34543\n"));
+        assert!(replay(&grid, 2, 0.0, &mut Vec::new()).is_err());
+        assert!(replay(&grid, 2, -1.0, &mut Vec::new()).is_err());
+        assert!(replay(&grid, 2, 1e-30, &mut Vec::new()).is_err());
+        // Stops at the first frame that doesn't fit, rather than overflowing the count.
+        let mut full = [0u8; 16];
+        assert!(replay(&grid, usize::MAX, f64::INFINITY, &mut &mut full[..]).is_err());
+
+        let pgm = frame.to_pgm(2);
+        let header = b"P5\n10 10\n255\n";
+        assert_eq!(&pgm[..header.len()], header);
+        assert_eq!(pgm.len(), header.len() + 100);
+        // Rows 2 and 3 of the image are both the second row of the grid.
+        let pixels = &pgm[header.len()..];
+        assert_eq!(
+            &pixels[20..30],
+            &[80, 80, 255, 255, 255, 255, 255, 255, 80, 80]
+        );
+        assert_eq!(&pixels[20..30], &pixels[30..40]);
+
+        let dir = std::env::temp_dir().join(format!("day11-frames-{}", std::process::id()));
+        let paths = export_frames(&grid, 3, &dir, 1)?;
+        assert_eq!(paths.len(), 4);
+        assert!(paths[3].ends_with("frame0003.pgm"));
+        assert_eq!(std::fs::read(&paths[1])?, frame.to_pgm(1));
+        std::fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+
+    fn random_grid(width: usize, height: usize, seed: u64) -> Grid {
+        let mut state = seed;
+        let values = (0..width * height)
+            .map(|_| {
+                state = state
+                    .wrapping_mul(6364136223846793005)
+                    .wrapping_add(1442695040888963407);
+                ((state >> 33) % 10) as u32
+            })
+            .collect();
+        Grid {
+            width,
+            height,
+            values,
+            topology: Topology::default(),
+        }
+    }
+
+    #[test]
+    fn stepper() -> anyhow::Result<()> {
+        for topology in [
+            Topology::default(),
+            Topology {
+                neighbors: Neighbors::Four,
+                wrap: true,
+            },
+        ] {
+            for (width, height) in [(37, 23), (2, 5), (1, 4)] {
+                let grid = random_grid(width, height, 7).with_topology(topology);
+                let (mut slow, mut fast) = (grid.clone(), Stepper::new(grid));
+                for _ in 0..300 {
+                    assert_eq!(slow.step(), fast.step());
+                    assert_eq!(slow.values, fast.grid.values);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Run with `cargo test --release bench_step -- --ignored --nocapture`.
+    #[test]
+    #[ignore]
+    fn bench_step() {
+        for size in [1000, 2000] {
+            let grid = random_grid(size, size, 42);
+            let steps = 10;
+            let mut slow = grid.clone();
+            let start = std::time::Instant::now();
+            let slow_flashes: usize = (0..steps).map(|_| slow.step()).sum();
+            let slow_time = start.elapsed();
+            let mut fast = Stepper::new(grid);
+            let start = std::time::Instant::now();
+            let fast_flashes: usize = (0..steps).map(|_| fast.step()).sum();
+            let fast_time = start.elapsed();
+            assert_eq!(
+                (slow_flashes, &slow.values),
+                (fast_flashes, &fast.grid.values)
+            );
+            println!(
+                "{0}x{0}, {1} steps: Grid::step {2:?}, Stepper::step {3:?} ({4:.1}x)",
+                size,
+                steps,
+                slow_time,
+                fast_time,
+                slow_time.as_secs_f64() / fast_time.as_secs_f64()
+            );
+        }
+    }
+
+    #[test]
+    fn shapes() -> anyhow::Result<()> {
+        // The puzzle's second example, embedded in a wider grid.
+        let grid = parse_input(
+            r"
+            1111100
+            1999100
+            1919100
+            1999100
+            1111100
+            ",
+        )?;
+        assert_eq!((grid.width, grid.height), (7, 5));
+        let mut stepped = grid.clone();
+        stepped.step();
+        assert_eq!(
+            stepped.values,
+            parse_input(
+                r"
+                3454311
+                4000411
+                5000511
+                4000411
+                3454311
+                "
+            )?
+            .values
+        );
+
+        let mut four = grid.clone().with_topology(Topology {
+            neighbors: Neighbors::Four,
+            wrap: false,
+        });
+        four.step();
+        assert_eq!(&four.values[..7], &[2, 3, 3, 3, 2, 1, 1]);
+
+        // Wrapping around, the top left corner borders the other three corners.
+        let mut torus = parse_input("900\n000\n000")?.with_topology(Topology {
+            neighbors: Neighbors::Eight,
+            wrap: true,
+        });
+        torus.step();
+        assert_eq!(torus.values, vec![0, 2, 2, 2, 2, 2, 2, 2, 2]);
+        // On a torus this thin, all eight offsets from one cell land on the
+        // other, but it is still only one neighbor.
+        let thin = Topology {
+            neighbors: Neighbors::Eight,
+            wrap: true,
+        };
+        let pair = parse_input("90")?.with_topology(thin);
+        assert_eq!(pair.cascades().next().unwrap().flashes.len(), 1);
+        let mut pair = pair;
+        pair.step();
+        assert_eq!(pair.values, vec![0, 2]);
+        let mut single = parse_input("9")?.with_topology(thin);
+        single.step();
+        assert_eq!(single.values, vec![0]);
+
+        assert!(parse_input("123\n45").is_err());
+        assert!(parse_input("12a").is_err());
         Ok(())
     }
 
@@ -96,7 +757,7 @@ mod test {
         let raw = std::fs::read_to_string("data/day11.input")?;
         let input = parse_input(&raw)?;
         assert_eq!(solve1(&input), 1741);
-        assert_eq!(solve2(&input), 440);
+        assert_eq!(solve2(&input), Some(440));
         Ok(())
     }
 }
@@ -82,11 +82,11 @@ fn adjacency_matrix(edges: &[Edge]) -> CaveMap {
 
 fn solve1(adj: &CaveMap) -> u64 {
     let mut vis = vec![0; adj.caves.len()];
-    count_paths(START, &adj, &mut vis, 0)
+    count_paths(START, adj, &mut vis, 0)
 }
 fn solve2(adj: &CaveMap) -> u64 {
     let mut vis = vec![0; adj.caves.len()];
-    count_paths(START, &adj, &mut vis, 1)
+    count_paths(START, adj, &mut vis, 1)
 }
 
 fn count_paths(cur: usize, adj: &CaveMap, vis: &mut [usize], allowed_repeats: usize) -> u64 {